@@ -0,0 +1,82 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AddressMode {
+    #[default]
+    FileOffset,
+    Mapped(u64),
+    Decimal,
+    Relative(usize),
+}
+
+impl AddressMode {
+    pub fn to_address(&self, offset: usize) -> i64 {
+        match self {
+            AddressMode::FileOffset | AddressMode::Decimal => offset as i64,
+            AddressMode::Mapped(base) => (*base as i64).wrapping_add(offset as i64),
+            AddressMode::Relative(anchor) => offset as i64 - *anchor as i64,
+        }
+    }
+
+    pub fn to_offset(&self, address: i64) -> Option<usize> {
+        let offset = match self {
+            AddressMode::FileOffset | AddressMode::Decimal => address,
+            AddressMode::Mapped(base) => address.wrapping_sub(*base as i64),
+            AddressMode::Relative(anchor) => address.checked_add(*anchor as i64)?,
+        };
+        usize::try_from(offset).ok()
+    }
+
+    pub fn format(&self, offset: usize) -> String {
+        let address = self.to_address(offset);
+        match self {
+            AddressMode::FileOffset | AddressMode::Mapped(_) => format!("{:08X}", address),
+            AddressMode::Decimal => format!("{:08}", address),
+            AddressMode::Relative(_) if address < 0 => format!("-{:07X}", address.unsigned_abs()),
+            AddressMode::Relative(_) => format!("+{:07X}", address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let modes = [
+            AddressMode::FileOffset,
+            AddressMode::Decimal,
+            AddressMode::Mapped(0x400000),
+            AddressMode::Relative(0x100),
+        ];
+        for mode in modes {
+            for offset in [0, 1, 0xff, 0x100, 0x12345] {
+                assert_eq!(mode.to_offset(mode.to_address(offset)), Some(offset));
+            }
+        }
+    }
+
+    #[test]
+    fn addresses() {
+        assert_eq!(AddressMode::Mapped(0x400000).to_address(0x10), 0x400010);
+        assert_eq!(AddressMode::Relative(0x100).to_address(0x80), -0x80);
+        assert_eq!(AddressMode::Relative(0x100).to_offset(-0x80), Some(0x80));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(AddressMode::FileOffset.to_offset(-1), None);
+        assert_eq!(AddressMode::Decimal.to_offset(-1), None);
+        assert_eq!(AddressMode::Mapped(0x400000).to_offset(0x3fffff), None);
+        assert_eq!(AddressMode::Relative(0x100).to_offset(-0x101), None);
+        assert_eq!(AddressMode::Relative(0x100).to_offset(i64::MAX), None);
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(AddressMode::FileOffset.format(0xabc), "00000ABC");
+        assert_eq!(AddressMode::Decimal.format(1234), "00001234");
+        assert_eq!(AddressMode::Mapped(0x400000).format(0x10), "00400010");
+        assert_eq!(AddressMode::Relative(0x100).format(0x80), "-0000080");
+        assert_eq!(AddressMode::Relative(0x100).format(0x180), "+0000080");
+    }
+}
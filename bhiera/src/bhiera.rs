@@ -1,40 +1,194 @@
 use std::collections::VecDeque;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
 
-use crate::{DataProvider, Geometry, View};
+use crate::edit::{map_offset, map_range, History};
+use crate::endian::{read_uint, sign_extend};
+use crate::navigation::Navigation;
+use crate::search::{max_ends, overlapping};
+use crate::{
+    AddressMode, Annotation, Bookmark, ColorScheme, DataProvider, Edit, EditedDataProvider, Endian,
+    Evaluator, Geometry, GuidDatabase, Movement, Notes, Result, SearchHit, Sidecar, Theme, View,
+    GUID_SIZE,
+};
 
 #[derive(Default)]
 pub struct Bhiera {
-    data_provider: Option<Box<dyn DataProvider>>,
+    data_provider: Option<Arc<EditedDataProvider>>,
     geometry: Geometry,
     view_y: u32,
+    view_height: u32,
     selection_begin: usize,
     selection_end: usize,
+    color_scheme: ColorScheme,
+    address_mode: AddressMode,
+    search_hits: Vec<SearchHit>,
+    /// Running maximum of the search hit ends.
+    search_hit_ends: Vec<usize>,
+    history: History,
+    navigation: Navigation,
+    sidecar: Option<Sidecar>,
+    notes: Notes,
+    guids: GuidDatabase,
 }
 
 impl Bhiera {
     pub fn new() -> Self {
         Self {
             data_provider: None,
+            guids: GuidDatabase::builtin(),
             ..Default::default()
         }
     }
 
     pub fn set_geometry(&mut self, geometry: &Geometry) {
+        let theme = *self.geometry.theme();
         self.geometry = *geometry;
+        self.geometry.set_theme(theme);
+    }
+
+    /// Applies one group of edits to a new version of the document and
+    /// keeps search hits and selection in place. Returns the inverse edits.
+    fn apply(&mut self, edits: &[Edit]) -> Result<Vec<Edit>> {
+        let provider = self
+            .data_provider
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no document loaded"))?;
+        let (provider, inverse) = provider.edit(edits)?;
+        self.data_provider = Some(Arc::new(provider));
+        self.search_hits.retain(|hit| {
+            !edits
+                .iter()
+                .any(|edit| edit.range.start < hit.range.end && hit.range.start < edit.range.end)
+        });
+        for hit in &mut self.search_hits {
+            let start = map_offset(edits, hit.range.start).unwrap_or(hit.range.start);
+            let shift = start as isize - hit.range.start as isize;
+            let shift_range = |range: &Range<usize>| {
+                (range.start as isize + shift) as usize..(range.end as isize + shift) as usize
+            };
+            hit.range = shift_range(&hit.range);
+            for capture in hit.captures.iter_mut().flatten() {
+                *capture = shift_range(capture);
+            }
+        }
+        self.search_hit_ends = max_ends(&self.search_hits);
+        for bookmark in &mut self.notes.bookmarks {
+            let offset = bookmark.offset;
+            bookmark.offset = map_range(edits, &inverse, offset..offset + 1).start;
+        }
+        for annotation in &mut self.notes.annotations {
+            annotation.range = map_range(edits, &inverse, annotation.range.clone());
+        }
+        for entry in self.navigation.entries_mut() {
+            *entry = map_range(edits, &inverse, entry.clone());
+        }
+        if let Some(first) = inverse.first() {
+            self.selection_begin = first.range.start;
+            self.selection_end = first.range.end;
+        }
+        Ok(inverse)
     }
 }
 
+/// Halfway between `color` and `background`, keeps annotated text readable.
+fn tint(color: (u8, u8, u8), background: (u8, u8, u8)) -> (u8, u8, u8) {
+    let mix = |a: u8, b: u8| ((a as u16 + b as u16) / 2) as u8;
+    (
+        mix(color.0, background.0),
+        mix(color.1, background.1),
+        mix(color.2, background.2),
+    )
+}
+
 pub trait Model {
     fn set_data_provider(&mut self, provider: impl DataProvider + 'static);
+    fn data_provider(&self) -> Option<&dyn DataProvider>;
+    fn snapshot(&self) -> Option<Arc<dyn DataProvider>>;
     fn get_view(&self, view_start: u32, view_height: u32) -> Option<View>;
     fn set_view_y(&mut self, y: u32);
+    fn set_view_height(&mut self, height: u32);
     fn set_selection_begin(&mut self, x: i32, y: i32);
     fn set_selection_end(&mut self, x: i32, y: i32);
+    fn byte_at(&self, x: i32, y: i32) -> usize;
+    fn set_cursor(&mut self, offset: usize);
+    fn move_cursor(&mut self, movement: Movement, extend: bool);
+    fn goto(&mut self, expression: &str) -> Result<usize>;
+    fn scroll_to_cursor(&self, view_height: u32) -> u32;
+    fn set_color_scheme(&mut self, color_scheme: ColorScheme);
+    fn set_theme(&mut self, theme: Theme);
+    fn theme(&self) -> Theme;
+    fn set_bytes_per_line(&mut self, bytes_per_line: usize);
+    fn bytes_per_line(&self) -> usize;
+    fn set_address_mode(&mut self, address_mode: AddressMode);
+    fn address_mode(&self) -> AddressMode;
+    fn cursor(&self) -> usize;
+    fn cursor_address(&self) -> String;
+    fn set_search_hits(&mut self, hits: Vec<SearchHit>);
+    fn search_hits(&self) -> &[SearchHit];
+    fn next_hit(&mut self) -> Option<SearchHit>;
+    fn previous_hit(&mut self) -> Option<SearchHit>;
+    fn select_hit(&mut self, index: usize) -> Option<SearchHit>;
+    fn selected_hit(&self) -> Option<usize>;
+    fn edit(&mut self, edits: Vec<Edit>) -> Result<()>;
+    fn undo(&mut self) -> Result<bool>;
+    fn redo(&mut self) -> Result<bool>;
+    fn save(&self, path: &Path) -> Result<()>;
+    fn content_size(&self) -> (u32, u32);
+    fn open_sidecar(&mut self, document: &Path) -> Result<()>;
+    fn set_content_hash(&mut self, provider: &Arc<dyn DataProvider>, sha256: String) -> Result<()>;
+    fn save_notes(&self) -> Result<()>;
+    fn bookmarks(&self) -> &[Bookmark];
+    fn add_bookmark(&mut self, name: String, color: (u8, u8, u8));
+    fn remove_bookmark(&mut self, index: usize);
+    fn select(&mut self, range: Range<usize>);
+    fn selection(&self) -> Range<usize>;
+    fn selection_or_document(&self) -> Range<usize>;
+    fn jump(&mut self, range: Range<usize>);
+    fn follow_pointer(
+        &mut self,
+        size: usize,
+        signed: bool,
+        endian: Endian,
+        mode: AddressMode,
+    ) -> Result<usize>;
+    fn navigate_back(&mut self) -> bool;
+    fn navigate_forward(&mut self) -> bool;
+    fn annotations(&self) -> &[Annotation];
+    fn annotations_at(&self, offset: usize) -> Vec<&Annotation>;
+    fn add_annotation(&mut self, label: String, comment: String, color: (u8, u8, u8));
+    fn extend_annotations(&mut self, annotations: Vec<Annotation>);
+    fn remove_annotation(&mut self, index: usize);
+    fn guids(&self) -> &GuidDatabase;
+    fn import_guids(&mut self, guids: GuidDatabase) -> usize;
+    fn guid_at(&self, offset: usize) -> Option<(Range<usize>, &str)>;
 }
 
 impl Model for Bhiera {
     fn set_data_provider(&mut self, provider: impl DataProvider + 'static) {
-        self.data_provider.replace(Box::new(provider));
+        self.data_provider
+            .replace(Arc::new(EditedDataProvider::new(Arc::new(provider))));
+        self.search_hits.clear();
+        self.search_hit_ends.clear();
+        self.history.clear();
+        self.navigation.clear();
+        self.sidecar = None;
+        self.notes = Notes::default();
+        self.set_cursor(0);
+    }
+
+    fn data_provider(&self) -> Option<&dyn DataProvider> {
+        self.data_provider
+            .as_deref()
+            .map(|provider| provider as &dyn DataProvider)
+    }
+
+    fn snapshot(&self) -> Option<Arc<dyn DataProvider>> {
+        self.data_provider
+            .clone()
+            .map(|provider| provider as Arc<dyn DataProvider>)
     }
 
     fn get_view(&self, view_start: u32, view_height: u32) -> Option<View> {
@@ -42,11 +196,51 @@ impl Model for Bhiera {
             let byte_offset = self.geometry.byte_offset(view_start);
             let line_count = self.geometry.line_count(view_height);
             let mut elements = VecDeque::new();
-            if let Some(bytes) = (*binary_data).get(byte_offset, line_count as usize * 16) {
+            if let Some(bytes) = (*binary_data).get(
+                byte_offset,
+                line_count as usize * self.geometry.bytes_per_line(),
+            ) {
                 elements.push_back(self.geometry.bg(view_height));
 
                 elements.push_back(self.geometry.offset_view_bg(view_height));
 
+                let visible = self.geometry.visible_bytes(view_start, view_height);
+                for annotation in self.notes.annotations.iter().filter(|annotation| {
+                    annotation.range.start < visible.end && visible.start < annotation.range.end
+                }) {
+                    elements.append(&mut self.geometry.highlight(
+                        view_start,
+                        view_height,
+                        annotation.range.start,
+                        annotation.range.end,
+                        tint(annotation.color, self.geometry.theme().background),
+                    ));
+                }
+                for hit in overlapping(&self.search_hits, &self.search_hit_ends, visible.clone()) {
+                    elements.append(&mut self.geometry.highlight(
+                        view_start,
+                        view_height,
+                        hit.range.start,
+                        hit.range.end,
+                        self.geometry.theme().highlight,
+                    ));
+                }
+
+                for bookmark in self
+                    .notes
+                    .bookmarks
+                    .iter()
+                    .filter(|bookmark| visible.contains(&bookmark.offset))
+                {
+                    elements.append(&mut self.geometry.highlight(
+                        view_start,
+                        view_height,
+                        bookmark.offset,
+                        bookmark.offset + 1,
+                        bookmark.color,
+                    ));
+                }
+
                 elements.append(&mut self.geometry.selection(
                     view_start,
                     view_height,
@@ -54,9 +248,13 @@ impl Model for Bhiera {
                     self.selection_end,
                 ));
 
-                elements.append(&mut self.geometry.offsets(byte_offset, bytes.len()));
+                elements.append(&mut self.geometry.offsets(
+                    byte_offset,
+                    bytes.len(),
+                    self.address_mode,
+                ));
 
-                elements.append(&mut self.geometry.text(bytes));
+                elements.append(&mut self.geometry.text(&bytes, self.color_scheme));
             };
 
             let cursors = self
@@ -72,6 +270,10 @@ impl Model for Bhiera {
         self.view_y = y;
     }
 
+    fn set_view_height(&mut self, height: u32) {
+        self.view_height = height;
+    }
+
     fn set_selection_begin(&mut self, x: i32, y: i32) {
         self.selection_begin = self
             .geometry
@@ -83,4 +285,395 @@ impl Model for Bhiera {
             .geometry
             .coordinate_to_byte(self.view_y, x as u32, y as u32);
     }
+
+    /// Offset of the byte under the view coordinates `x` and `y`.
+    fn byte_at(&self, x: i32, y: i32) -> usize {
+        self.geometry
+            .coordinate_to_byte(self.view_y, x.max(0) as u32, y.max(0) as u32)
+    }
+
+    fn set_cursor(&mut self, offset: usize) {
+        self.selection_begin = offset;
+        self.selection_end = offset;
+    }
+
+    /// Moves the cursor, with `extend` the selection grows from its
+    /// beginning instead. A selection may end behind the last byte.
+    fn move_cursor(&mut self, movement: Movement, extend: bool) {
+        let len = self
+            .data_provider
+            .as_ref()
+            .map_or(0, |provider| provider.len());
+        let last = match extend {
+            true => len,
+            false => len.saturating_sub(1),
+        };
+        let page_lines = self.geometry.line_count(self.view_height);
+        let offset = movement.apply(
+            self.selection_end,
+            last,
+            page_lines,
+            self.geometry.bytes_per_line(),
+        );
+        match extend {
+            true => self.selection_end = offset,
+            false => self.set_cursor(offset),
+        }
+    }
+
+    fn goto(&mut self, expression: &str) -> Result<usize> {
+        let provider = self
+            .data_provider
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no document loaded"))?;
+        let evaluator = Evaluator {
+            provider,
+            address_mode: self.address_mode,
+            cursor: self.selection_end,
+            selection_len: self.selection_begin.abs_diff(self.selection_end),
+        };
+        let offset = evaluator.evaluate(expression)?;
+        self.jump(offset..offset);
+        Ok(offset)
+    }
+
+    fn scroll_to_cursor(&self, view_height: u32) -> u32 {
+        self.geometry
+            .scroll_to(self.view_y, view_height, self.selection_end)
+    }
+
+    fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        self.color_scheme = color_scheme;
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.geometry.set_theme(theme);
+    }
+
+    fn theme(&self) -> Theme {
+        *self.geometry.theme()
+    }
+
+    /// Changes the row width of the hex view, the content size changes
+    /// with it.
+    fn set_bytes_per_line(&mut self, bytes_per_line: usize) {
+        self.geometry.set_bytes_per_line(bytes_per_line);
+    }
+
+    fn bytes_per_line(&self) -> usize {
+        self.geometry.bytes_per_line()
+    }
+
+    fn set_address_mode(&mut self, address_mode: AddressMode) {
+        self.address_mode = address_mode;
+    }
+
+    fn address_mode(&self) -> AddressMode {
+        self.address_mode
+    }
+
+    fn cursor(&self) -> usize {
+        self.selection_end
+    }
+
+    fn cursor_address(&self) -> String {
+        self.address_mode.format(self.cursor())
+    }
+
+    fn set_search_hits(&mut self, hits: Vec<SearchHit>) {
+        self.search_hit_ends = max_ends(&hits);
+        self.search_hits = hits;
+    }
+
+    fn search_hits(&self) -> &[SearchHit] {
+        &self.search_hits
+    }
+
+    fn next_hit(&mut self) -> Option<SearchHit> {
+        let position = std::cmp::min(self.selection_begin, self.selection_end);
+        let hit = self
+            .search_hits
+            .iter()
+            .find(|hit| hit.range.start > position)
+            .or(self.search_hits.first())
+            .cloned()?;
+        self.jump(hit.range.clone());
+        Some(hit)
+    }
+
+    fn previous_hit(&mut self) -> Option<SearchHit> {
+        let position = std::cmp::min(self.selection_begin, self.selection_end);
+        let hit = self
+            .search_hits
+            .iter()
+            .rev()
+            .find(|hit| hit.range.start < position)
+            .or(self.search_hits.last())
+            .cloned()?;
+        self.jump(hit.range.clone());
+        Some(hit)
+    }
+
+    fn select_hit(&mut self, index: usize) -> Option<SearchHit> {
+        let hit = self.search_hits.get(index).cloned()?;
+        self.jump(hit.range.clone());
+        Some(hit)
+    }
+
+    fn selected_hit(&self) -> Option<usize> {
+        self.search_hits.iter().position(|hit| {
+            hit.range.start == self.selection_begin && hit.range.end == self.selection_end
+        })
+    }
+
+    /// Applies `edits` as one undoable step.
+    fn edit(&mut self, edits: Vec<Edit>) -> Result<()> {
+        let inverse = self.apply(&edits)?;
+        self.history.record(inverse);
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<bool> {
+        let edits = match self.history.pop_undo() {
+            Some(edits) => edits,
+            None => return Ok(false),
+        };
+        let inverse = self.apply(&edits)?;
+        self.history.push_redo(inverse);
+        Ok(true)
+    }
+
+    fn redo(&mut self) -> Result<bool> {
+        let edits = match self.history.pop_redo() {
+            Some(edits) => edits,
+            None => return Ok(false),
+        };
+        let inverse = self.apply(&edits)?;
+        self.history.push_undo(inverse);
+        Ok(true)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let provider = self
+            .data_provider
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no document loaded"))?;
+        fs::write(path, provider.get(0, provider.len()).unwrap_or_default())?;
+        Ok(())
+    }
+
+    fn content_size(&self) -> (u32, u32) {
+        let len = self
+            .data_provider
+            .as_ref()
+            .map_or(0, |provider| provider.len());
+        (self.geometry.width(), self.geometry.height(len))
+    }
+
+    /// Loads the notes stored next to `document` under its file name, the
+    /// content hash follows with `set_content_hash`.
+    fn open_sidecar(&mut self, document: &Path) -> Result<()> {
+        let sidecar = Sidecar::new(document);
+        self.notes = sidecar.load()?;
+        self.sidecar = Some(sidecar);
+        Ok(())
+    }
+
+    /// Sets the content hash the notes are stored under, ignored when the
+    /// document changed since `provider` was taken. A renamed document
+    /// without notes of its own picks up the notes stored under the hash.
+    fn set_content_hash(&mut self, provider: &Arc<dyn DataProvider>, sha256: String) -> Result<()> {
+        let current = self
+            .snapshot()
+            .is_some_and(|current| Arc::ptr_eq(&current, provider));
+        let sidecar = match &mut self.sidecar {
+            Some(sidecar) if current => sidecar,
+            _ => return Ok(()),
+        };
+        sidecar.set_sha256(sha256);
+        if self.notes == Notes::default() {
+            self.notes = sidecar.load()?;
+        }
+        Ok(())
+    }
+
+    fn save_notes(&self) -> Result<()> {
+        match &self.sidecar {
+            Some(sidecar) => sidecar.save(&self.notes),
+            None => Ok(()),
+        }
+    }
+
+    fn bookmarks(&self) -> &[Bookmark] {
+        &self.notes.bookmarks
+    }
+
+    /// Bookmarks the cursor, the list stays sorted by offset.
+    fn add_bookmark(&mut self, name: String, color: (u8, u8, u8)) {
+        let offset = self.cursor();
+        let index = self
+            .notes
+            .bookmarks
+            .partition_point(|bookmark| bookmark.offset <= offset);
+        self.notes.bookmarks.insert(
+            index,
+            Bookmark {
+                name,
+                offset,
+                color,
+            },
+        );
+    }
+
+    fn remove_bookmark(&mut self, index: usize) {
+        if index < self.notes.bookmarks.len() {
+            self.notes.bookmarks.remove(index);
+        }
+    }
+
+    fn select(&mut self, range: Range<usize>) {
+        self.selection_begin = range.start;
+        self.selection_end = range.end;
+    }
+
+    fn selection(&self) -> Range<usize> {
+        std::cmp::min(self.selection_begin, self.selection_end)
+            ..std::cmp::max(self.selection_begin, self.selection_end)
+    }
+
+    /// The selection, an empty one stands for the whole document.
+    fn selection_or_document(&self) -> Range<usize> {
+        match self.selection() {
+            range if range.is_empty() => 0..self.data_provider.as_ref().map_or(0, |p| p.len()),
+            range => range,
+        }
+    }
+
+    fn annotations(&self) -> &[Annotation] {
+        &self.notes.annotations
+    }
+
+    /// Annotations containing `offset`, innermost first.
+    fn annotations_at(&self, offset: usize) -> Vec<&Annotation> {
+        let mut annotations: Vec<&Annotation> = self
+            .notes
+            .annotations
+            .iter()
+            .filter(|annotation| annotation.range.contains(&offset))
+            .collect();
+        annotations.sort_by_key(|annotation| annotation.range.len());
+        annotations
+    }
+
+    /// Annotates the selection, or the byte at the cursor if nothing is
+    /// selected.
+    fn add_annotation(&mut self, label: String, comment: String, color: (u8, u8, u8)) {
+        let mut range = self.selection();
+        if range.is_empty() {
+            range = range.start..range.start + 1;
+        }
+        self.extend_annotations(vec![Annotation {
+            label,
+            comment,
+            range,
+            color,
+        }]);
+    }
+
+    fn extend_annotations(&mut self, annotations: Vec<Annotation>) {
+        self.notes.annotations.extend(annotations);
+        self.notes
+            .annotations
+            .sort_by_key(|annotation| annotation.range.start);
+    }
+
+    fn remove_annotation(&mut self, index: usize) {
+        if index < self.notes.annotations.len() {
+            self.notes.annotations.remove(index);
+        }
+    }
+
+    fn guids(&self) -> &GuidDatabase {
+        &self.guids
+    }
+
+    /// Adds names to the GUID database, returning how many were imported.
+    fn import_guids(&mut self, guids: GuidDatabase) -> usize {
+        self.guids.merge(guids)
+    }
+
+    /// The known GUID covering `offset` and its name.
+    fn guid_at(&self, offset: usize) -> Option<(Range<usize>, &str)> {
+        let provider = self
+            .data_provider
+            .as_deref()
+            .filter(|provider| offset < provider.len())?;
+        let start = offset.saturating_sub(GUID_SIZE - 1);
+        let bytes = provider.get(start, offset + GUID_SIZE - start)?;
+        (0..=offset - start).rev().find_map(|index| {
+            let name = self.guids.name_at(&bytes[index..])?;
+            Some((start + index..start + index + GUID_SIZE, name))
+        })
+    }
+
+    /// Selects `range` and remembers the current selection for
+    /// `navigate_back`.
+    fn jump(&mut self, range: Range<usize>) {
+        let current = self.selection();
+        if current != range {
+            self.navigation.record(current);
+        }
+        self.select(range);
+    }
+
+    /// Reads a value at the cursor and jumps to the offset it points to, as
+    /// interpreted by `mode`. Signed values can point backwards from a
+    /// relative anchor.
+    fn follow_pointer(
+        &mut self,
+        size: usize,
+        signed: bool,
+        endian: Endian,
+        mode: AddressMode,
+    ) -> Result<usize> {
+        let provider = self
+            .data_provider
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no document loaded"))?;
+        let value = provider
+            .get(self.selection_end, size)
+            .filter(|bytes| bytes.len() == size)
+            .map(|bytes| read_uint(&bytes, endian))
+            .map(|value| match signed {
+                true => sign_extend(value, size),
+                false => value as i64,
+            })
+            .ok_or_else(|| anyhow::anyhow!("not enough bytes at the cursor"))?;
+        let offset = mode
+            .to_offset(value)
+            .filter(|offset| *offset < provider.len())
+            .ok_or_else(|| anyhow::anyhow!("{:#x} points outside the document", value))?;
+        self.jump(offset..offset);
+        Ok(offset)
+    }
+
+    fn navigate_back(&mut self) -> bool {
+        match self.navigation.back(self.selection()) {
+            Some(range) => {
+                self.select(range);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn navigate_forward(&mut self) -> bool {
+        match self.navigation.forward(self.selection()) {
+            Some(range) => {
+                self.select(range);
+                true
+            }
+            None => false,
+        }
+    }
 }
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use crate::{Error, Theme};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteClass {
+    Null,
+    Full,
+    Whitespace,
+    Printable,
+    Control,
+    High,
+}
+
+impl ByteClass {
+    pub fn of(byte: u8) -> Self {
+        match byte {
+            0x00 => ByteClass::Null,
+            0xFF => ByteClass::Full,
+            b'\t' | b'\n' | 0x0B | 0x0C | b'\r' | b' ' => ByteClass::Whitespace,
+            b if b.is_ascii_graphic() => ByteClass::Printable,
+            b if b.is_ascii() => ByteClass::Control,
+            _ => ByteClass::High,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    Mono,
+    #[default]
+    Class,
+    Gradient,
+}
+
+impl ColorScheme {
+    pub const ALL: [ColorScheme; 3] =
+        [ColorScheme::Class, ColorScheme::Gradient, ColorScheme::Mono];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorScheme::Mono => "mono",
+            ColorScheme::Class => "class",
+            ColorScheme::Gradient => "gradient",
+        }
+    }
+
+    pub fn color(&self, byte: u8, theme: &Theme) -> (u8, u8, u8) {
+        match self {
+            ColorScheme::Mono => theme.foreground,
+            ColorScheme::Class => theme.class_color(ByteClass::of(byte)),
+            ColorScheme::Gradient => gradient(theme.gradient_low, theme.gradient_high, byte),
+        }
+    }
+}
+
+impl FromStr for ColorScheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ColorScheme::ALL
+            .into_iter()
+            .find(|scheme| scheme.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow::anyhow!("unknown color scheme: {}", s))
+    }
+}
+
+fn gradient(low: (u8, u8, u8), high: (u8, u8, u8), byte: u8) -> (u8, u8, u8) {
+    let lerp = |a: u8, b: u8| (a as u32 * (255 - byte as u32) + b as u32 * byte as u32) / 255;
+    (
+        lerp(low.0, high.0) as u8,
+        lerp(low.1, high.1) as u8,
+        lerp(low.2, high.2) as u8,
+    )
+}
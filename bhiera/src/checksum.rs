@@ -0,0 +1,295 @@
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+
+use crc::{Crc, Digest, CRC_16_ARC, CRC_16_IBM_3740, CRC_16_KERMIT, CRC_16_MODBUS, CRC_16_XMODEM};
+use crc::{CRC_32_ISCSI, CRC_32_ISO_HDLC};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest as _, Sha256, Sha384};
+
+use crate::chunks::for_each_chunk;
+use crate::DataProvider;
+
+static CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+static CRC32C: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+static CRC16_ARC: Crc<u16> = Crc::<u16>::new(&CRC_16_ARC);
+static CRC16_CCITT_FALSE: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_3740);
+static CRC16_KERMIT: Crc<u16> = Crc::<u16>::new(&CRC_16_KERMIT);
+static CRC16_MODBUS: Crc<u16> = Crc::<u16>::new(&CRC_16_MODBUS);
+static CRC16_XMODEM: Crc<u16> = Crc::<u16>::new(&CRC_16_XMODEM);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChecksumKind {
+    Crc32,
+    Crc32c,
+    Crc16Arc,
+    Crc16CcittFalse,
+    Crc16Kermit,
+    Crc16Modbus,
+    Crc16Xmodem,
+    Sum8,
+    TwosComplement8,
+    Adler32,
+    Fletcher16,
+    Fletcher32,
+    Md5,
+    Sha1,
+    Sha256,
+    Sha384,
+}
+
+impl ChecksumKind {
+    pub const ALL: [ChecksumKind; 16] = [
+        ChecksumKind::Crc32,
+        ChecksumKind::Crc32c,
+        ChecksumKind::Crc16Arc,
+        ChecksumKind::Crc16CcittFalse,
+        ChecksumKind::Crc16Kermit,
+        ChecksumKind::Crc16Modbus,
+        ChecksumKind::Crc16Xmodem,
+        ChecksumKind::Sum8,
+        ChecksumKind::TwosComplement8,
+        ChecksumKind::Adler32,
+        ChecksumKind::Fletcher16,
+        ChecksumKind::Fletcher32,
+        ChecksumKind::Md5,
+        ChecksumKind::Sha1,
+        ChecksumKind::Sha256,
+        ChecksumKind::Sha384,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumKind::Crc32 => "CRC-32",
+            ChecksumKind::Crc32c => "CRC-32C",
+            ChecksumKind::Crc16Arc => "CRC-16/ARC",
+            ChecksumKind::Crc16CcittFalse => "CRC-16/CCITT-FALSE",
+            ChecksumKind::Crc16Kermit => "CRC-16/KERMIT",
+            ChecksumKind::Crc16Modbus => "CRC-16/MODBUS",
+            ChecksumKind::Crc16Xmodem => "CRC-16/XMODEM",
+            ChecksumKind::Sum8 => "Sum-8",
+            ChecksumKind::TwosComplement8 => "Two's complement-8",
+            ChecksumKind::Adler32 => "Adler-32",
+            ChecksumKind::Fletcher16 => "Fletcher-16",
+            ChecksumKind::Fletcher32 => "Fletcher-32",
+            ChecksumKind::Md5 => "MD5",
+            ChecksumKind::Sha1 => "SHA-1",
+            ChecksumKind::Sha256 => "SHA-256",
+            ChecksumKind::Sha384 => "SHA-384",
+        }
+    }
+
+    fn hasher(&self) -> Hasher {
+        match self {
+            ChecksumKind::Crc32 => Hasher::Crc32(CRC32.digest()),
+            ChecksumKind::Crc32c => Hasher::Crc32(CRC32C.digest()),
+            ChecksumKind::Crc16Arc => Hasher::Crc16(CRC16_ARC.digest()),
+            ChecksumKind::Crc16CcittFalse => Hasher::Crc16(CRC16_CCITT_FALSE.digest()),
+            ChecksumKind::Crc16Kermit => Hasher::Crc16(CRC16_KERMIT.digest()),
+            ChecksumKind::Crc16Modbus => Hasher::Crc16(CRC16_MODBUS.digest()),
+            ChecksumKind::Crc16Xmodem => Hasher::Crc16(CRC16_XMODEM.digest()),
+            ChecksumKind::Sum8 => Hasher::Sum8(0),
+            ChecksumKind::TwosComplement8 => Hasher::TwosComplement8(0),
+            ChecksumKind::Adler32 => Hasher::Adler32(1, 0),
+            ChecksumKind::Fletcher16 => Hasher::Fletcher16(0, 0),
+            ChecksumKind::Fletcher32 => Hasher::Fletcher32(0, 0, None),
+            ChecksumKind::Md5 => Hasher::Md5(Md5::new()),
+            ChecksumKind::Sha1 => Hasher::Sha1(Sha1::new()),
+            ChecksumKind::Sha256 => Hasher::Sha256(Sha256::new()),
+            ChecksumKind::Sha384 => Hasher::Sha384(Sha384::new()),
+        }
+    }
+}
+
+enum Hasher {
+    Crc16(Digest<'static, u16>),
+    Crc32(Digest<'static, u32>),
+    Sum8(u8),
+    TwosComplement8(u8),
+    Adler32(u32, u32),
+    Fletcher16(u16, u16),
+    /// Sums over little endian words and the odd byte of the last chunk.
+    Fletcher32(u32, u32, Option<u8>),
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+}
+
+impl Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Crc16(digest) => digest.update(bytes),
+            Hasher::Crc32(digest) => digest.update(bytes),
+            Hasher::Sum8(sum) | Hasher::TwosComplement8(sum) => {
+                *sum = bytes.iter().fold(*sum, |sum, byte| sum.wrapping_add(*byte));
+            }
+            Hasher::Adler32(a, b) => {
+                for byte in bytes {
+                    *a = (*a + *byte as u32) % 65521;
+                    *b = (*b + *a) % 65521;
+                }
+            }
+            Hasher::Fletcher16(a, b) => {
+                for byte in bytes {
+                    *a = (*a + *byte as u16) % 255;
+                    *b = (*b + *a) % 255;
+                }
+            }
+            Hasher::Fletcher32(a, b, pending) => {
+                let mut bytes = bytes;
+                if let Some(low) = pending.take() {
+                    match bytes.split_first() {
+                        Some((high, rest)) => {
+                            fletcher32_word(a, b, u16::from_le_bytes([low, *high]));
+                            bytes = rest;
+                        }
+                        None => *pending = Some(low),
+                    }
+                }
+                let mut words = bytes.chunks_exact(2);
+                for word in &mut words {
+                    fletcher32_word(a, b, u16::from_le_bytes([word[0], word[1]]));
+                }
+                if let [odd] = words.remainder() {
+                    *pending = Some(*odd);
+                }
+            }
+            Hasher::Md5(digest) => digest.update(bytes),
+            Hasher::Sha1(digest) => digest.update(bytes),
+            Hasher::Sha256(digest) => digest.update(bytes),
+            Hasher::Sha384(digest) => digest.update(bytes),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Hasher::Crc16(digest) => format!("{:04x}", digest.finalize()),
+            Hasher::Crc32(digest) => format!("{:08x}", digest.finalize()),
+            Hasher::Sum8(sum) => format!("{:02x}", sum),
+            Hasher::TwosComplement8(sum) => format!("{:02x}", sum.wrapping_neg()),
+            Hasher::Adler32(a, b) => format!("{:08x}", (b << 16) | a),
+            Hasher::Fletcher16(a, b) => format!("{:04x}", (b << 8) | a),
+            Hasher::Fletcher32(mut a, mut b, pending) => {
+                // An odd trailing byte is padded with zero.
+                if let Some(low) = pending {
+                    fletcher32_word(&mut a, &mut b, low as u16);
+                }
+                format!("{:08x}", (b << 16) | a)
+            }
+            Hasher::Md5(digest) => to_hex(&digest.finalize()),
+            Hasher::Sha1(digest) => to_hex(&digest.finalize()),
+            Hasher::Sha256(digest) => to_hex(&digest.finalize()),
+            Hasher::Sha384(digest) => to_hex(&digest.finalize()),
+        }
+    }
+}
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    CRC32.checksum(bytes)
+}
+
+fn fletcher32_word(a: &mut u32, b: &mut u32, word: u16) {
+    *a = (*a + word as u32) % 65535;
+    *b = (*b + *a) % 65535;
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Computes all checksums of `range` in a single pass, the result only
+/// covers part of it when `cancel` is set.
+pub fn checksums<F>(
+    provider: &dyn DataProvider,
+    range: Range<usize>,
+    cancel: &AtomicBool,
+    progress: F,
+) -> Vec<(ChecksumKind, String)>
+where
+    F: FnMut(usize),
+{
+    let mut hashers: Vec<Hasher> = ChecksumKind::ALL.iter().map(|kind| kind.hasher()).collect();
+    for_each_chunk(provider, range, cancel, progress, |_, bytes| {
+        for hasher in hashers.iter_mut() {
+            hasher.update(bytes);
+        }
+    });
+    ChecksumKind::ALL
+        .into_iter()
+        .zip(hashers)
+        .map(|(kind, hasher)| (kind, hasher.finalize()))
+        .collect()
+}
+
+/// Compares a checksum with a value pasted by the user, ignoring case,
+/// whitespace, `:` and `-` separators and a `0x` prefix. Any other
+/// character makes the value not match.
+pub fn checksum_matches(checksum: &str, expected: &str) -> bool {
+    let expected = expected.trim();
+    let expected = expected
+        .strip_prefix("0x")
+        .or_else(|| expected.strip_prefix("0X"))
+        .unwrap_or(expected);
+    let mut digits = String::with_capacity(expected.len());
+    for c in expected.chars() {
+        match c {
+            c if c.is_ascii_hexdigit() => digits.push(c),
+            c if c.is_whitespace() || c == ':' || c == '-' => {}
+            _ => return false,
+        }
+    }
+    !digits.is_empty() && checksum.eq_ignore_ascii_case(&digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn checksum(kind: ChecksumKind, bytes: &[u8]) -> String {
+        let provider = MemoryDataProvider::new(bytes.to_vec());
+        checksums(&provider, 0..bytes.len(), &AtomicBool::new(false), |_| {})
+            .into_iter()
+            .find(|(found, _)| *found == kind)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn check_values() {
+        assert_eq!(checksum(ChecksumKind::Crc32, b"123456789"), "cbf43926");
+        assert_eq!(checksum(ChecksumKind::Adler32, b"123456789"), "091e01de");
+        assert_eq!(checksum(ChecksumKind::Adler32, b"abcde"), "05c801f0");
+        assert_eq!(checksum(ChecksumKind::Adler32, b"Wikipedia"), "11e60398");
+        assert_eq!(checksum(ChecksumKind::Fletcher16, b"abcde"), "c8f0");
+        assert_eq!(checksum(ChecksumKind::Fletcher16, b"abcdef"), "2057");
+        assert_eq!(checksum(ChecksumKind::Fletcher32, b"abcde"), "f04fc729");
+        assert_eq!(checksum(ChecksumKind::Fletcher32, b"abcdef"), "56502d2a");
+    }
+
+    #[test]
+    fn fletcher32_odd_chunks() {
+        let mut whole = ChecksumKind::Fletcher32.hasher();
+        whole.update(b"abcdefgh");
+        let mut split = ChecksumKind::Fletcher32.hasher();
+        for chunk in [&b"a"[..], b"bcd", b"", b"efg", b"h"] {
+            split.update(chunk);
+        }
+        assert_eq!(whole.finalize(), split.finalize());
+    }
+
+    #[test]
+    fn matches() {
+        assert!(checksum_matches("cbf43926", "CBF43926"));
+        assert!(checksum_matches("cbf43926", " 0xcbf43926 "));
+        assert!(checksum_matches("cbf43926", "cb:f4:39:26"));
+        assert!(checksum_matches("cbf43926", "cbf4-3926"));
+        assert!(checksum_matches("cbf43926", "cb f4 39 26"));
+        assert!(!checksum_matches("12", "zz12"));
+        assert!(!checksum_matches("cbf43926", "cbf43926h"));
+        assert!(!checksum_matches("cbf43926", "cbf4392"));
+        assert!(!checksum_matches("", ""));
+        assert!(!checksum_matches("", "0x"));
+    }
+}
@@ -0,0 +1,32 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::DataProvider;
+
+/// Bytes read at once by the scans over a document.
+pub(crate) const CHUNK_SIZE: usize = 1 << 20;
+
+/// Calls `f` with the offset and bytes of each chunk of `range`, which is
+/// clipped to the document. `progress` is called with the number of bytes
+/// done after every chunk, the walk stops early when `cancel` is set.
+pub(crate) fn for_each_chunk<P, F>(
+    provider: &dyn DataProvider,
+    range: Range<usize>,
+    cancel: &AtomicBool,
+    mut progress: P,
+    mut f: F,
+) where
+    P: FnMut(usize),
+    F: FnMut(usize, &[u8]),
+{
+    let end = std::cmp::min(range.end, provider.len());
+    let mut offset = range.start;
+    while offset < end && !cancel.load(Ordering::Relaxed) {
+        let size = std::cmp::min(CHUNK_SIZE, end - offset);
+        if let Some(bytes) = provider.get(offset, size) {
+            f(offset, &bytes);
+        }
+        offset += size;
+        progress(offset - range.start);
+    }
+}
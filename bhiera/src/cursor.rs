@@ -0,0 +1,94 @@
+/// Keyboard cursor movements, all in bytes of the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Movement {
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    LineStart,
+    LineEnd,
+    DocumentStart,
+    DocumentEnd,
+}
+
+impl Movement {
+    /// New cursor position, `last` is the largest allowed offset,
+    /// `page_lines` the number of lines on screen and `bytes_per_line`
+    /// the row width.
+    pub fn apply(
+        &self,
+        cursor: usize,
+        last: usize,
+        page_lines: usize,
+        bytes_per_line: usize,
+    ) -> usize {
+        let page = std::cmp::max(page_lines, 1) * bytes_per_line;
+        let line_start = cursor - cursor % bytes_per_line;
+        let target = match self {
+            Movement::Left => cursor.saturating_sub(1),
+            Movement::Right => cursor.saturating_add(1),
+            Movement::Up => cursor.saturating_sub(bytes_per_line),
+            Movement::Down if cursor + bytes_per_line > last => cursor,
+            Movement::Down => cursor + bytes_per_line,
+            Movement::PageUp => cursor.saturating_sub(page),
+            Movement::PageDown => cursor.saturating_add(page),
+            Movement::LineStart => line_start,
+            Movement::LineEnd => line_start + bytes_per_line - 1,
+            Movement::DocumentStart => 0,
+            Movement::DocumentEnd => last,
+        };
+        std::cmp::min(target, last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 100 byte document shown as 16 byte lines, 4 lines per page.
+    fn apply(movement: Movement, cursor: usize) -> usize {
+        movement.apply(cursor, 99, 4, 16)
+    }
+
+    #[test]
+    fn document_start() {
+        assert_eq!(apply(Movement::Left, 0), 0);
+        assert_eq!(apply(Movement::Up, 5), 0);
+        assert_eq!(apply(Movement::Up, 20), 4);
+        assert_eq!(apply(Movement::PageUp, 70), 6);
+        assert_eq!(apply(Movement::PageUp, 40), 0);
+        assert_eq!(apply(Movement::LineStart, 0), 0);
+        assert_eq!(apply(Movement::DocumentStart, 50), 0);
+    }
+
+    #[test]
+    fn document_end() {
+        assert_eq!(apply(Movement::Right, 99), 99);
+        assert_eq!(apply(Movement::Right, 98), 99);
+        assert_eq!(apply(Movement::Down, 83), 99);
+        // No byte below in the last partial line, so the cursor stays.
+        assert_eq!(apply(Movement::Down, 84), 84);
+        assert_eq!(apply(Movement::PageDown, 40), 99);
+        assert_eq!(apply(Movement::PageDown, 10), 74);
+        assert_eq!(apply(Movement::LineEnd, 97), 99);
+        assert_eq!(apply(Movement::LineEnd, 20), 31);
+        assert_eq!(apply(Movement::DocumentEnd, 0), 99);
+    }
+
+    #[test]
+    fn extremes() {
+        assert_eq!(
+            Movement::Right.apply(usize::MAX, usize::MAX, 4, 16),
+            usize::MAX
+        );
+        assert_eq!(
+            Movement::PageDown.apply(usize::MAX - 1, usize::MAX, 4, 16),
+            usize::MAX
+        );
+        // A page is at least one line.
+        assert_eq!(Movement::PageDown.apply(0, 99, 0, 16), 16);
+        assert_eq!(Movement::Down.apply(0, 0, 4, 16), 0);
+    }
+}
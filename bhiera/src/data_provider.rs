@@ -1,4 +1,6 @@
-pub trait DataProvider {
+use std::borrow::Cow;
+
+pub trait DataProvider: Send + Sync {
     fn len(&self) -> usize;
-    fn get(&self, offset: usize, count: usize) -> Option<&[u8]>;
+    fn get(&self, offset: usize, count: usize) -> Option<Cow<'_, [u8]>>;
 }
@@ -0,0 +1,180 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use iced_x86::{Decoder, DecoderOptions, Formatter, Instruction, IntelFormatter};
+
+use crate::{AddressMode, DataProvider, Error, Result};
+
+/// Longest x86 instruction.
+const MAX_INSTRUCTION_SIZE: usize = 15;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Bitness {
+    Bits16,
+    Bits32,
+    #[default]
+    Bits64,
+}
+
+impl Bitness {
+    pub const ALL: [Bitness; 3] = [Bitness::Bits16, Bitness::Bits32, Bitness::Bits64];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bitness::Bits16 => "16-bit",
+            Bitness::Bits32 => "32-bit",
+            Bitness::Bits64 => "64-bit",
+        }
+    }
+
+    fn bits(&self) -> u32 {
+        match self {
+            Bitness::Bits16 => 16,
+            Bitness::Bits32 => 32,
+            Bitness::Bits64 => 64,
+        }
+    }
+}
+
+impl FromStr for Bitness {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Bitness::ALL
+            .into_iter()
+            .find(|bitness| bitness.name() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown bitness: {}", s))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub range: Range<usize>,
+    /// Intel syntax, with addresses as given by the address mode.
+    pub text: String,
+    /// File offset of a near branch or call target within the document.
+    pub target: Option<usize>,
+}
+
+/// Decodes up to `count` instructions starting at `offset`. Instructions
+/// are placed at the addresses of `address_mode`, so a mapped base address
+/// gives the addresses the code runs at.
+pub fn disassemble(
+    provider: &dyn DataProvider,
+    offset: usize,
+    count: usize,
+    bitness: Bitness,
+    address_mode: AddressMode,
+) -> Vec<DecodedInstruction> {
+    let available = provider.len().saturating_sub(offset);
+    let size = std::cmp::min(available, count * MAX_INSTRUCTION_SIZE);
+    let bytes = match provider.get(offset, size) {
+        Some(bytes) => bytes,
+        None => return Vec::new(),
+    };
+    let ip = address_mode.to_address(offset) as u64;
+    let mut decoder = Decoder::with_ip(bitness.bits(), &bytes, ip, DecoderOptions::NONE);
+    let mut formatter = IntelFormatter::new();
+    formatter.options_mut().set_uppercase_hex(false);
+    formatter
+        .options_mut()
+        .set_space_after_operand_separator(true);
+    let mut instruction = Instruction::default();
+    let mut instructions = Vec::with_capacity(count);
+    while decoder.can_decode() && instructions.len() < count {
+        let start = offset + decoder.position();
+        decoder.decode_out(&mut instruction);
+        let mut text = String::new();
+        match instruction.is_invalid() {
+            true => text.push_str("(bad)"),
+            false => formatter.format(&instruction, &mut text),
+        }
+        instructions.push(DecodedInstruction {
+            range: start..start + instruction.len(),
+            text,
+            target: branch_target(&instruction, bitness, address_mode, provider.len()),
+        });
+    }
+    instructions
+}
+
+fn branch_target(
+    instruction: &Instruction,
+    bitness: Bitness,
+    address_mode: AddressMode,
+    len: usize,
+) -> Option<usize> {
+    let mut target = instruction.near_branch_target();
+    if target == 0 {
+        return None;
+    }
+    if bitness == Bitness::Bits16 {
+        // Near branches stay within the code segment.
+        target |= instruction.ip() & !0xffff;
+    }
+    address_mode
+        .to_offset(target as i64)
+        .filter(|offset| *offset < len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn decode(
+        bytes: &[u8],
+        bitness: Bitness,
+        address_mode: AddressMode,
+    ) -> Vec<DecodedInstruction> {
+        let provider = MemoryDataProvider::new(bytes.to_vec());
+        disassemble(&provider, 0, 16, bitness, address_mode)
+    }
+
+    #[test]
+    fn mapped_targets() {
+        // call +0, jmp -7 back to the call.
+        let bytes = b"\xe8\x00\x00\x00\x00\xeb\xf9";
+        let instructions = decode(bytes, Bitness::Bits32, AddressMode::Mapped(0x401000));
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].range, 0..5);
+        assert_eq!(instructions[0].target, Some(5));
+        assert!(instructions[0].text.contains("401005"));
+        assert_eq!(instructions[1].target, Some(0));
+    }
+
+    #[test]
+    fn targets_outside_the_document() {
+        // jmp far past the end, jmp before the start.
+        let bytes = b"\xe9\x00\x10\x00\x00\xeb\x80";
+        let instructions = decode(bytes, Bitness::Bits64, AddressMode::FileOffset);
+        assert_eq!(instructions[0].target, None);
+        assert_eq!(instructions[1].target, None);
+        let instructions = decode(b"\xeb\x80", Bitness::Bits32, AddressMode::Mapped(0x1000));
+        assert_eq!(instructions[0].target, None);
+    }
+
+    #[test]
+    fn targets_wrap_within_16_bit_segment() {
+        let mut bytes = vec![0x90; 0x10000];
+        // jmp +0x20 near the end of the segment wraps to its start.
+        bytes[0xfff0..0xfff2].copy_from_slice(b"\xeb\x20");
+        let provider = MemoryDataProvider::new(bytes);
+        let instructions = disassemble(
+            &provider,
+            0xfff0,
+            1,
+            Bitness::Bits16,
+            AddressMode::Mapped(0x10000),
+        );
+        assert_eq!(instructions[0].target, Some(0x12));
+    }
+
+    #[test]
+    fn truncated_instruction() {
+        let instructions = decode(b"\x90\xe8\x00", Bitness::Bits64, AddressMode::FileOffset);
+        assert_eq!(instructions[0].text, "nop");
+        assert_eq!(instructions[1].text, "(bad)");
+        assert_eq!(instructions[1].target, None);
+    }
+}
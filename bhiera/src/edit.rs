@@ -0,0 +1,95 @@
+use std::ops::Range;
+
+/// Replaces `range` of the document with `bytes`, an insertion when the
+/// range is empty and a deletion when `bytes` is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub bytes: Vec<u8>,
+}
+
+impl Edit {
+    pub fn new(range: Range<usize>, bytes: Vec<u8>) -> Self {
+        Self { range, bytes }
+    }
+
+    /// Change in document length caused by this edit.
+    pub fn delta(&self) -> isize {
+        self.bytes.len() as isize - self.range.len() as isize
+    }
+}
+
+/// Maps `offset` through `edits`, `None` if it lies inside a replaced range.
+/// Insertions at `offset` move it behind the inserted bytes.
+pub fn map_offset(edits: &[Edit], offset: usize) -> Option<usize> {
+    let mut delta = 0isize;
+    for edit in edits {
+        if offset < edit.range.start {
+            break;
+        }
+        if offset < edit.range.end {
+            return None;
+        }
+        delta += edit.delta();
+    }
+    Some((offset as isize + delta) as usize)
+}
+
+/// Maps `range` through `edits`, ends inside a replaced range snap to the
+/// bounds of the replacement. `inverse` are the edits returned by
+/// `EditedDataProvider::edit`.
+pub fn map_range(edits: &[Edit], inverse: &[Edit], range: Range<usize>) -> Range<usize> {
+    let replacement = |offset: usize| {
+        let index = edits
+            .iter()
+            .position(|edit| edit.range.contains(&offset))
+            .unwrap();
+        inverse[index].range.clone()
+    };
+    let start = map_offset(edits, range.start).unwrap_or_else(|| replacement(range.start).start);
+    if range.is_empty() {
+        return start..start;
+    }
+    let last = range.end - 1;
+    let end = match map_offset(edits, last) {
+        Some(offset) => offset + 1,
+        None => replacement(last).end,
+    };
+    start..std::cmp::max(start, end)
+}
+
+/// Undo and redo stacks, each entry is one group of edits.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo: Vec<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+}
+
+impl History {
+    /// Records a new change, which discards everything that was undone.
+    pub fn record(&mut self, inverse: Vec<Edit>) {
+        self.undo.push(inverse);
+        self.redo.clear();
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Vec<Edit>> {
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Vec<Edit>> {
+        self.redo.pop()
+    }
+
+    pub fn push_undo(&mut self, inverse: Vec<Edit>) {
+        self.undo.push(inverse);
+    }
+
+    pub fn push_redo(&mut self, inverse: Vec<Edit>) {
+        self.redo.push(inverse);
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
@@ -0,0 +1,202 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::{DataProvider, Edit, Result};
+
+/// Where the bytes of a piece live.
+#[derive(Clone)]
+enum Source {
+    Base,
+    Inserted(Arc<[u8]>),
+}
+
+/// A range of bytes of one source.
+#[derive(Clone)]
+struct Piece {
+    source: Source,
+    range: Range<usize>,
+}
+
+/// A piece table of edits over a base document. Editing shares all bytes
+/// with the previous version, so its cost depends on the number of edits
+/// made rather than on the document size.
+#[derive(Clone)]
+pub struct EditedDataProvider {
+    base: Arc<dyn DataProvider>,
+    pieces: Vec<Piece>,
+    /// Document offset of each piece.
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl EditedDataProvider {
+    pub fn new(base: Arc<dyn DataProvider>) -> Self {
+        let mut provider = Self {
+            base,
+            pieces: Vec::new(),
+            starts: Vec::new(),
+            len: 0,
+        };
+        let len = provider.base.len();
+        provider.push(Piece {
+            source: Source::Base,
+            range: 0..len,
+        });
+        provider
+    }
+
+    /// Applies `edits`, sorted and non-overlapping ranges of the document,
+    /// at once. Returns the edited document together with the edits that
+    /// undo the change.
+    pub fn edit(&self, edits: &[Edit]) -> Result<(Self, Vec<Edit>)> {
+        let mut result = Self {
+            base: self.base.clone(),
+            pieces: Vec::with_capacity(self.pieces.len() + 2 * edits.len()),
+            starts: Vec::with_capacity(self.pieces.len() + 2 * edits.len()),
+            len: 0,
+        };
+        let mut inverse = Vec::with_capacity(edits.len());
+        let mut position = 0;
+        for edit in edits {
+            if edit.range.start < position || edit.range.end > self.len {
+                anyhow::bail!(
+                    "invalid edit {:#X}..{:#X}",
+                    edit.range.start,
+                    edit.range.end
+                );
+            }
+            self.copy_to(&mut result, position..edit.range.start);
+            let start = result.len;
+            result.push(Piece {
+                source: Source::Inserted(edit.bytes.as_slice().into()),
+                range: 0..edit.bytes.len(),
+            });
+            let replaced = self
+                .get(edit.range.start, edit.range.len())
+                .filter(|_| !edit.range.is_empty())
+                .map(Cow::into_owned)
+                .unwrap_or_default();
+            inverse.push(Edit::new(start..result.len, replaced));
+            position = edit.range.end;
+        }
+        self.copy_to(&mut result, position..self.len);
+        Ok((result, inverse))
+    }
+
+    fn push(&mut self, piece: Piece) {
+        if piece.range.is_empty() {
+            return;
+        }
+        self.starts.push(self.len);
+        self.len += piece.range.len();
+        self.pieces.push(piece);
+    }
+
+    /// Index of the piece holding `offset`, which must be in the document.
+    fn piece_at(&self, offset: usize) -> usize {
+        self.starts.partition_point(|start| *start <= offset) - 1
+    }
+
+    /// Appends the pieces covering `range` of this document to `other`.
+    fn copy_to(&self, other: &mut Self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        for index in self.piece_at(range.start)..self.pieces.len() {
+            let start = self.starts[index];
+            if start >= range.end {
+                break;
+            }
+            let piece = &self.pieces[index];
+            let skip = range.start.saturating_sub(start);
+            let end = std::cmp::min(piece.range.len(), range.end - start);
+            other.push(Piece {
+                source: piece.source.clone(),
+                range: piece.range.start + skip..piece.range.start + end,
+            });
+        }
+    }
+
+    fn bytes<'a>(&'a self, piece: &'a Piece, range: Range<usize>) -> Cow<'a, [u8]> {
+        let range = piece.range.start + range.start..piece.range.start + range.end;
+        match &piece.source {
+            Source::Base => self.base.get(range.start, range.len()).unwrap_or_default(),
+            Source::Inserted(bytes) => Cow::Borrowed(&bytes[range]),
+        }
+    }
+}
+
+impl DataProvider for EditedDataProvider {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Borrows the bytes when they lie in one piece and copies them
+    /// otherwise.
+    fn get(&self, offset: usize, count: usize) -> Option<Cow<'_, [u8]>> {
+        if offset >= self.len {
+            return None;
+        }
+        let end = std::cmp::min(offset.saturating_add(count), self.len);
+        let first = self.piece_at(offset);
+        let piece_end = self.starts[first] + self.pieces[first].range.len();
+        if end <= piece_end {
+            let start = self.starts[first];
+            return Some(self.bytes(&self.pieces[first], offset - start..end - start));
+        }
+        let mut bytes = Vec::with_capacity(end - offset);
+        for index in first..self.pieces.len() {
+            let start = self.starts[index];
+            if start >= end {
+                break;
+            }
+            let piece = &self.pieces[index];
+            let range = offset.saturating_sub(start)..std::cmp::min(piece.range.len(), end - start);
+            bytes.extend_from_slice(&self.bytes(piece, range));
+        }
+        Some(Cow::Owned(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn contents(provider: &EditedDataProvider) -> Vec<u8> {
+        provider
+            .get(0, provider.len())
+            .map(Cow::into_owned)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn edit_and_undo() {
+        let base =
+            EditedDataProvider::new(Arc::new(MemoryDataProvider::new(b"0123456789".to_vec())));
+        let (edited, inverse) = base
+            .edit(&[
+                Edit::new(0..0, b"ab".to_vec()),
+                Edit::new(2..5, b"X".to_vec()),
+                Edit::new(9..10, Vec::new()),
+            ])
+            .unwrap();
+        assert_eq!(contents(&edited), b"ab01X5678");
+        assert_eq!(edited.get(3, 3).unwrap(), &b"1X5"[..]);
+        assert!(matches!(edited.get(5, 3).unwrap(), Cow::Borrowed(_)));
+        assert_eq!(edited.get(8, 10).unwrap(), &b"8"[..]);
+        assert!(edited.get(9, 1).is_none());
+        let (undone, _) = edited.edit(&inverse).unwrap();
+        assert_eq!(contents(&undone), b"0123456789");
+    }
+
+    #[test]
+    fn invalid_edits() {
+        let base = EditedDataProvider::new(Arc::new(MemoryDataProvider::new(b"0123".to_vec())));
+        assert!(base.edit(&[Edit::new(2..5, Vec::new())]).is_err());
+        assert!(base
+            .edit(&[Edit::new(2..3, Vec::new()), Edit::new(1..2, Vec::new())])
+            .is_err());
+    }
+}
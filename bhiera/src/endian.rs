@@ -0,0 +1,51 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// Reads an unsigned integer of `bytes.len()` bytes, at most 8.
+pub(crate) fn read_uint(bytes: &[u8], endian: Endian) -> u64 {
+    let fold = |value: u64, byte: &u8| value << 8 | *byte as u64;
+    match endian {
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+        Endian::Big => bytes.iter().fold(0, fold),
+    }
+}
+
+/// Writes the low `size` bytes of `value`.
+pub(crate) fn write_uint(value: u64, size: usize, endian: Endian) -> Vec<u8> {
+    let bytes = value.to_le_bytes()[..size].to_vec();
+    match endian {
+        Endian::Little => bytes,
+        Endian::Big => bytes.into_iter().rev().collect(),
+    }
+}
+
+/// Interprets the low `size` bytes of `value` as two's complement.
+pub(crate) fn sign_extend(value: u64, size: usize) -> i64 {
+    let shift = 64 - size * 8;
+    ((value << shift) as i64) >> shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_order() {
+        assert_eq!(read_uint(&[0x12, 0x34, 0x56], Endian::Little), 0x563412);
+        assert_eq!(read_uint(&[0x12, 0x34, 0x56], Endian::Big), 0x123456);
+        assert_eq!(write_uint(0x123456, 3, Endian::Little), [0x56, 0x34, 0x12]);
+        assert_eq!(write_uint(0x123456, 2, Endian::Big), [0x34, 0x56]);
+        assert_eq!(read_uint(&[], Endian::Big), 0);
+    }
+
+    #[test]
+    fn signed() {
+        assert_eq!(sign_extend(0xFFFF_FFF0, 4), -16);
+        assert_eq!(sign_extend(0x7FFF, 2), 0x7FFF);
+        assert_eq!(sign_extend(0x80, 1), -128);
+        assert_eq!(sign_extend(u64::MAX, 8), -1);
+    }
+}
@@ -0,0 +1,259 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::endian::read_uint;
+use crate::{AddressMode, DataProvider, Endian, Result};
+
+/// Parentheses and reads nested deeper are rejected instead of
+/// overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Values available to a goto expression. All positions are addresses in
+/// the current address mode.
+pub struct Evaluator<'a> {
+    pub provider: &'a dyn DataProvider,
+    pub address_mode: AddressMode,
+    pub cursor: usize,
+    pub selection_len: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    /// Evaluates `expression` and returns the file offset it points to.
+    ///
+    /// Supported are hex (`0x10`) and decimal literals, `+ - * /`,
+    /// parentheses, `cursor`, `end`, `sizeof` (selection length) and
+    /// reads like `u32le[0x10]`. A leading `+` or `-` jumps relative
+    /// to the cursor.
+    pub fn evaluate(&self, expression: &str) -> Result<usize> {
+        let mut parser = Parser {
+            context: self,
+            chars: expression.chars().peekable(),
+            depth: 0,
+        };
+        parser.skip_whitespace();
+        let relative = matches!(parser.chars.peek(), Some('+') | Some('-'));
+        let mut value = parser.expression()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.chars.next() {
+            anyhow::bail!("unexpected '{}'", c);
+        }
+        if relative {
+            value = value
+                .checked_add(self.address_mode.to_address(self.cursor))
+                .ok_or_else(|| anyhow::anyhow!("overflow"))?;
+        }
+        self.address_mode
+            .to_offset(value)
+            .filter(|offset| *offset < self.provider.len())
+            .ok_or_else(|| anyhow::anyhow!("{:#X} is outside the document", value))
+    }
+
+    fn read(&self, name: &str, address: i64) -> Result<i64> {
+        let (size, endian) =
+            read_width(name).ok_or_else(|| anyhow::anyhow!("unknown identifier: {}", name))?;
+        let bytes = self
+            .address_mode
+            .to_offset(address)
+            .and_then(|offset| self.provider.get(offset, size))
+            .filter(|bytes| bytes.len() == size)
+            .ok_or_else(|| anyhow::anyhow!("cannot read {} at {:#X}", name, address))?;
+        Ok(read_uint(&bytes, endian) as i64)
+    }
+}
+
+fn read_width(name: &str) -> Option<(usize, Endian)> {
+    match name {
+        "u8" => Some((1, Endian::Little)),
+        "u16le" => Some((2, Endian::Little)),
+        "u16be" => Some((2, Endian::Big)),
+        "u32le" => Some((4, Endian::Little)),
+        "u32be" => Some((4, Endian::Big)),
+        "u64le" => Some((8, Endian::Little)),
+        "u64be" => Some((8, Endian::Big)),
+        _ => None,
+    }
+}
+
+struct Parser<'a, 'b> {
+    context: &'a Evaluator<'a>,
+    chars: Peekable<Chars<'b>>,
+    depth: usize,
+}
+
+impl<'a, 'b> Parser<'a, 'b> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expression(&mut self) -> Result<i64> {
+        self.skip_whitespace();
+        let mut value = match self.chars.next_if(|c| *c == '+' || *c == '-') {
+            Some('-') => self.term()?.wrapping_neg(),
+            _ => self.term()?,
+        };
+        loop {
+            self.skip_whitespace();
+            match self.chars.next_if(|c| *c == '+' || *c == '-') {
+                Some('+') => value = value.wrapping_add(self.term()?),
+                Some(_) => value = value.wrapping_sub(self.term()?),
+                None => return Ok(value),
+            }
+        }
+    }
+
+    /// An expression inside parentheses or brackets.
+    fn nested(&mut self) -> Result<i64> {
+        if self.depth >= MAX_DEPTH {
+            anyhow::bail!("expression nested too deeply");
+        }
+        self.depth += 1;
+        let value = self.expression();
+        self.depth -= 1;
+        value
+    }
+
+    fn term(&mut self) -> Result<i64> {
+        let mut value = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.next_if(|c| *c == '*' || *c == '/') {
+                Some('*') => value = value.wrapping_mul(self.factor()?),
+                Some(_) => {
+                    value = value
+                        .checked_div(self.factor()?)
+                        .ok_or_else(|| anyhow::anyhow!("division by zero"))?
+                }
+                None => return Ok(value),
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Result<i64> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('(') => {
+                self.chars.next();
+                let value = self.nested()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.word();
+                match name.as_str() {
+                    "cursor" => Ok(self.context.address_mode.to_address(self.context.cursor)),
+                    "end" => Ok(self
+                        .context
+                        .address_mode
+                        .to_address(self.context.provider.len())),
+                    "sizeof" => Ok(self.context.selection_len as i64),
+                    _ if read_width(&name).is_none() => {
+                        anyhow::bail!("unknown identifier: {}", name)
+                    }
+                    _ => {
+                        self.skip_whitespace();
+                        self.expect('[')?;
+                        let address = self.nested()?;
+                        self.expect(']')?;
+                        self.context.read(&name, address)
+                    }
+                }
+            }
+            Some(c) => anyhow::bail!("unexpected '{}'", c),
+            None => anyhow::bail!("unexpected end of expression"),
+        }
+    }
+
+    fn number(&mut self) -> Result<i64> {
+        let word = self.word();
+        let value = match word.strip_prefix("0x").or(word.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16)? as i64,
+            None => word.parse()?,
+        };
+        Ok(value)
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            word.push(c);
+        }
+        word
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => anyhow::bail!("expected '{}', found '{}'", expected, c),
+            None => anyhow::bail!("expected '{}'", expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn evaluate(expression: &str) -> Result<usize> {
+        let mut bytes = vec![0; 0x100];
+        bytes[0x10..0x14].copy_from_slice(&[0x20, 0, 0, 0]);
+        let provider = MemoryDataProvider::new(bytes);
+        let evaluator = Evaluator {
+            provider: &provider,
+            address_mode: AddressMode::FileOffset,
+            cursor: 8,
+            selection_len: 4,
+        };
+        evaluator.evaluate(expression)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(evaluate("2 + 3 * 4").unwrap(), 14);
+        assert_eq!(evaluate("(2 + 3) * 4").unwrap(), 20);
+        assert_eq!(evaluate("20 - 4 / 2").unwrap(), 18);
+        assert_eq!(evaluate("2 * 3 - 1").unwrap(), 5);
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(evaluate("0x10").unwrap(), 16);
+        assert_eq!(evaluate("0X1f").unwrap(), 31);
+        assert_eq!(evaluate("16").unwrap(), 16);
+        assert_eq!(evaluate("0x10 + 10").unwrap(), 26);
+        assert!(evaluate("0xZZ").is_err());
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(evaluate("cursor + sizeof").unwrap(), 12);
+        assert_eq!(evaluate("end - 1").unwrap(), 0xFF);
+        assert_eq!(evaluate("+4").unwrap(), 12);
+        assert_eq!(evaluate("-8").unwrap(), 0);
+        assert!(evaluate("end").is_err());
+        assert!(evaluate("nothing").is_err());
+    }
+
+    #[test]
+    fn reads() {
+        assert_eq!(evaluate("u32le[0x10]").unwrap(), 0x20);
+        assert_eq!(evaluate("u8[0x10] + 1").unwrap(), 0x21);
+        assert_eq!(evaluate("u16be[0x0F]").unwrap(), 0x20);
+        assert_eq!(evaluate("u32le[u8[0x10] - 0x10]").unwrap(), 0x20);
+        assert!(evaluate("u32be[0x10]").is_err());
+        assert!(evaluate("u64le[0xFC]").is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let expression = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(evaluate(&expression).is_err());
+        let expression = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(evaluate(&expression).unwrap(), 1);
+    }
+}
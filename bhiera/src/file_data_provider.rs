@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
@@ -32,7 +33,7 @@ impl DataProvider for FileDataProvider {
         self.bytes.len()
     }
 
-    fn get(&self, offset: usize, count: usize) -> Option<&[u8]> {
+    fn get(&self, offset: usize, count: usize) -> Option<Cow<'_, [u8]>> {
         if offset >= self.len() {
             return None;
         }
@@ -42,7 +43,7 @@ impl DataProvider for FileDataProvider {
             end = self.len();
         }
         match self.bytes.get(offset..end) {
-            Some(bytes) => Some(bytes),
+            Some(bytes) => Some(Cow::Borrowed(bytes)),
             None => None,
         }
     }
@@ -1,9 +1,15 @@
 use std::{
     cmp::{max, min},
     collections::VecDeque,
+    ops::Range,
 };
 
-use crate::Element;
+use crate::{AddressMode, ColorScheme, Element, Theme};
+
+/// Row width of a freshly created geometry.
+const DEFAULT_BYTES_PER_LINE: usize = 16;
+/// The hex view leaves an extra gap after every group of this many bytes.
+const GROUP: usize = 8;
 
 #[derive(Clone, Copy, Default)]
 pub struct Geometry {
@@ -11,6 +17,8 @@ pub struct Geometry {
     char_height: u32,
     hex_byte_width: u32,
     offset_view_width: u32,
+    bytes_per_line: usize,
+    theme: Theme,
     /* below values are calculated */
     hex_view_start: u32,
     hex_view_width: u32,
@@ -28,30 +36,58 @@ impl Geometry {
         hex_byte_width: u32,
         offset_view_width: u32,
     ) -> Self {
-        let hex_view_start = offset_view_width;
-        let hex_view_width = (char_width + hex_byte_width) * 16;
-        let hex_view_end = hex_view_start + hex_view_width;
-        let char_view_start = hex_view_end + char_width * 2;
-        let char_view_width = char_width * 16;
-        let char_view_end = char_view_start + char_view_width;
-        let width = char_view_end + char_width;
-        Self {
+        let mut geometry = Self {
             char_width,
             char_height,
             hex_byte_width,
             offset_view_width,
-            hex_view_start,
-            hex_view_width,
-            hex_view_end,
-            char_view_start,
-            char_view_width,
-            char_view_end,
-            width,
-        }
+            bytes_per_line: DEFAULT_BYTES_PER_LINE,
+            ..Default::default()
+        };
+        geometry.layout();
+        geometry
+    }
+
+    fn layout(&mut self) {
+        let n = self.bytes_per_line as u32;
+        let gaps = (self.bytes_per_line as u32 - 1) / GROUP as u32;
+        self.hex_view_start = self.offset_view_width;
+        self.hex_view_width =
+            (self.char_width + self.hex_byte_width) * n + self.char_width * gaps - self.char_width;
+        self.hex_view_end = self.hex_view_start + self.hex_view_width;
+        self.char_view_start = self.hex_view_end + self.char_width * 2;
+        self.char_view_width = self.char_width * n;
+        self.char_view_end = self.char_view_start + self.char_view_width;
+        self.width = self.char_view_end + self.char_width;
+    }
+
+    pub fn bytes_per_line(&self) -> usize {
+        self.bytes_per_line
+    }
+
+    /// Changes the row width, at least one byte per line.
+    pub fn set_bytes_per_line(&mut self, bytes_per_line: usize) {
+        self.bytes_per_line = max(bytes_per_line, 1);
+        self.layout();
+    }
+
+    /// Horizontal position of byte `index` of a line within the hex view.
+    fn hex_x(&self, index: usize) -> u32 {
+        self.hex_view_start
+            + index as u32 * (self.char_width + self.hex_byte_width)
+            + (index / GROUP) as u32 * self.char_width
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
     }
 
     pub fn height(&self, byte_count: usize) -> u32 {
-        let total_line_count = (byte_count + 15) / 16;
+        let total_line_count = byte_count.div_ceil(self.bytes_per_line);
         self.char_height * total_line_count as u32
     }
 
@@ -61,7 +97,7 @@ impl Geometry {
 
     pub fn byte_offset(&self, view_start: u32) -> usize {
         let start_line = self.line_index(view_start);
-        start_line * 16
+        start_line * self.bytes_per_line
     }
 
     fn line_index(&self, view_start: u32) -> usize {
@@ -72,8 +108,26 @@ impl Geometry {
         (view_height / self.char_height) as usize
     }
 
+    pub fn visible_bytes(&self, view_start: u32, view_height: u32) -> Range<usize> {
+        let byte_offset = self.byte_offset(view_start);
+        byte_offset..byte_offset + self.line_count(view_height) * self.bytes_per_line
+    }
+
+    /// Returns the view start that keeps the line of `offset` visible,
+    /// scrolling as little as possible.
+    pub fn scroll_to(&self, view_start: u32, view_height: u32, offset: usize) -> u32 {
+        let y = (offset / self.bytes_per_line) as u32 * self.char_height;
+        if y < view_start {
+            y
+        } else if y + self.char_height > view_start + view_height {
+            (y + self.char_height).saturating_sub(view_height)
+        } else {
+            view_start
+        }
+    }
+
     pub fn char_view_width(&self) -> u32 {
-        self.char_width * 16
+        self.char_view_width
     }
 
     pub fn calc_cursor(
@@ -85,18 +139,14 @@ impl Geometry {
         let byte_offset = self.byte_offset(view_start);
         let line_count = self.line_count(view_height);
         let mut cursors = Vec::new();
-        if current_byte >= byte_offset && current_byte < (byte_offset + line_count * 16) {
+        if current_byte >= byte_offset
+            && current_byte < (byte_offset + line_count * self.bytes_per_line)
+        {
             let cursor_width = 2;
             let cursor_height = self.char_height;
-            let line_index = (current_byte - byte_offset) / 16;
-            let byte_index = (current_byte - byte_offset) % 16;
-            let combo_width = self.char_width + self.hex_byte_width;
-            let x = self.hex_view_start
-                + if byte_index < 8 {
-                    byte_index as u32 * combo_width
-                } else {
-                    byte_index as u32 * combo_width + self.char_width
-                };
+            let line_index = (current_byte - byte_offset) / self.bytes_per_line;
+            let byte_index = (current_byte - byte_offset) % self.bytes_per_line;
+            let x = self.hex_x(byte_index);
             let y: u32 = { line_index as u32 * self.char_height };
             cursors.push((x, y, cursor_width, cursor_height));
             let x = self.char_view_start + byte_index as u32 * self.char_width;
@@ -108,38 +158,47 @@ impl Geometry {
     pub fn coordinate_to_byte(&self, y_offset: u32, x: u32, y: u32) -> usize {
         let line_index = (y_offset + y) / self.char_height;
         let combo_width = self.char_width + self.hex_byte_width;
+        let group_width = combo_width * GROUP as u32 + self.char_width;
+        let last = self.bytes_per_line - 1;
         let byte_index = match x {
             x if x < self.hex_view_start => 0,
-            x if x < self.hex_view_end => (x - self.hex_view_start) / combo_width,
-            x if x < (self.hex_view_end + self.char_view_start) / 2 => 15,
-            x if x < self.char_view_start + self.char_width / 2 => 0,
-            x if x < self.width() - self.char_width => {
-                (x - self.char_view_start + self.char_width / 2) / self.char_width
+            x if x < self.hex_view_end => {
+                let x = x - self.hex_view_start;
+                let group = (x / group_width) as usize;
+                let index = min((x % group_width / combo_width) as usize, GROUP - 1);
+                min(group * GROUP + index, last)
             }
-            _ => 15,
+            x if x < (self.hex_view_end + self.char_view_start) / 2 => last,
+            x if x < self.char_view_start + self.char_width / 2 => 0,
+            x if x < self.width() - self.char_width => min(
+                ((x - self.char_view_start + self.char_width / 2) / self.char_width) as usize,
+                last,
+            ),
+            _ => last,
         };
-        (line_index * 16 + byte_index) as usize
+        line_index as usize * self.bytes_per_line + byte_index
     }
 
     fn hex_coordinate(&self, byte_offset: usize) -> (u32, u32, u32, u32) {
-        let x = {
-            let byte_index = byte_offset % 16;
-            self.hex_view_start
-                + byte_index as u32 * (self.hex_byte_width + self.char_width)
-                + if byte_index < 8 { 0 } else { self.char_width }
-        };
-        let y = (byte_offset / 16) as u32 * self.char_height;
+        let x = self.hex_x(byte_offset % self.bytes_per_line);
+        let y = (byte_offset / self.bytes_per_line) as u32 * self.char_height;
         (x, y, self.hex_byte_width, self.char_height)
     }
 
     fn char_coordinate(&self, byte_offset: usize) -> (u32, u32, u32, u32) {
-        let x = self.char_view_start + (byte_offset % 16) as u32 * self.char_width;
-        let y = (byte_offset / 16) as u32 * self.char_height;
+        let x = self.char_view_start + (byte_offset % self.bytes_per_line) as u32 * self.char_width;
+        let y = (byte_offset / self.bytes_per_line) as u32 * self.char_height;
         (x, y, self.char_width, self.char_height)
     }
 
     pub fn bg(&self, height: u32) -> Element {
-        Element::rectangle(0, 0, self.width() as i32, height as i32, (255, 255, 255))
+        Element::rectangle(
+            0,
+            0,
+            self.width() as i32,
+            height as i32,
+            self.theme.background,
+        )
     }
 
     pub fn offset_view_bg(&self, height: u32) -> Element {
@@ -148,7 +207,7 @@ impl Geometry {
             0,
             self.offset_view_width as i32,
             height as i32,
-            (224, 224, 224),
+            self.theme.offset_background,
         )
     }
 
@@ -158,18 +217,40 @@ impl Geometry {
         view_height: u32,
         selection_begin: usize,
         selection_end: usize,
+    ) -> VecDeque<Element> {
+        if selection_begin <= selection_end {
+            self.highlight(
+                view_start,
+                view_height,
+                selection_begin,
+                selection_end,
+                self.theme.selection,
+            )
+        } else {
+            self.highlight(
+                view_start,
+                view_height,
+                selection_end,
+                selection_begin,
+                self.theme.selection,
+            )
+        }
+    }
+
+    pub fn highlight(
+        &self,
+        view_start: u32,
+        view_height: u32,
+        selection_begin: usize,
+        selection_end: usize,
+        color: (u8, u8, u8),
     ) -> VecDeque<Element> {
         let mut elements = VecDeque::new();
         if selection_begin == selection_end {
             return elements;
         }
-        let (selection_begin, selection_end) = if selection_begin <= selection_end {
-            (selection_begin, selection_end)
-        } else {
-            (selection_end, selection_begin)
-        };
         let byte_offset = self.byte_offset(view_start);
-        let capacity = self.line_count(view_height) * 16;
+        let capacity = self.line_count(view_height) * self.bytes_per_line;
         let visible_begin = max(selection_begin, byte_offset);
         let visible_end = min(selection_end, byte_offset + capacity);
         if visible_begin >= visible_end {
@@ -184,7 +265,7 @@ impl Geometry {
                 y,
                 (x2 + width) as i32,
                 y,
-                (0, 220, 220),
+                color,
                 self.char_height,
             );
             elements.push_back(element);
@@ -194,7 +275,7 @@ impl Geometry {
                 y1 as i32,
                 (self.hex_view_end - x1) as i32,
                 self.char_height as i32,
-                (0, 220, 220),
+                color,
             );
             elements.push_back(element);
             let element = Element::rectangle(
@@ -202,7 +283,7 @@ impl Geometry {
                 (y1 + self.char_height) as i32,
                 self.hex_view_width as i32,
                 (y2 - y1 - self.char_height) as i32,
-                (0, 220, 220),
+                color,
             );
             elements.push_back(element);
             let element = Element::rectangle(
@@ -210,7 +291,7 @@ impl Geometry {
                 y2 as i32,
                 (x2 + width - self.hex_view_start) as i32,
                 self.char_height as i32,
-                (0, 220, 220),
+                color,
             );
             elements.push_back(element);
         }
@@ -222,7 +303,7 @@ impl Geometry {
                 y1 as i32,
                 (x2 + width - x1) as i32,
                 self.char_height as i32,
-                (0, 220, 220),
+                color,
             );
             elements.push_back(element);
         } else if y2 > y1 {
@@ -231,7 +312,7 @@ impl Geometry {
                 y1 as i32,
                 (self.char_view_end - x1) as i32,
                 self.char_height as i32,
-                (0, 220, 220),
+                color,
             );
             elements.push_back(element);
             let element = Element::rectangle(
@@ -239,7 +320,7 @@ impl Geometry {
                 (y1 + self.char_height) as i32,
                 self.char_view_width as i32,
                 (y2 - y1 - self.char_height) as i32,
-                (0, 220, 220),
+                color,
             );
             elements.push_back(element);
             let element = Element::rectangle(
@@ -247,44 +328,50 @@ impl Geometry {
                 y2 as i32,
                 (x2 + width - self.char_view_start) as i32,
                 self.char_height as i32,
-                (0, 220, 220),
+                color,
             );
             elements.push_back(element);
         }
         elements
     }
 
-    pub fn offsets(&self, offset: usize, size: usize) -> VecDeque<Element> {
+    pub fn offsets(
+        &self,
+        offset: usize,
+        size: usize,
+        address_mode: AddressMode,
+    ) -> VecDeque<Element> {
         let mut elements = VecDeque::new();
-        for (line, line_offset) in (0..size).step_by(16).enumerate() {
-            let text = format!("{:08X}", offset + line_offset);
+        for (line, line_offset) in (0..size).step_by(self.bytes_per_line).enumerate() {
+            let text = address_mode.format(offset + line_offset);
             let y = line * self.char_height as usize;
-            let element = Element::byte(text, 0, y as i32, (117, 117, 117));
+            let element = Element::byte(text, 0, y as i32, self.theme.offset_text);
             elements.push_back(element);
         }
         elements
     }
 
-    pub fn text(&self, bytes: &[u8]) -> VecDeque<Element> {
+    pub fn text(&self, bytes: &[u8], color_scheme: ColorScheme) -> VecDeque<Element> {
         let mut elements = VecDeque::new();
         for (i, byte) in bytes.iter().enumerate() {
-            let line = i / 16;
-            let index = i % 16;
+            let line = i / self.bytes_per_line;
+            let index = i % self.bytes_per_line;
             let text = format!("{:02X}", byte);
-            let mut x =
-                self.hex_view_start + (self.char_width + self.hex_byte_width) * index as u32;
-            if index >= 8 {
-                x += self.char_width;
-            }
+            let x = self.hex_x(index);
             let y = line as u32 * self.char_height;
 
-            let element = Element::byte(text, x as i32, y as i32, (0, 0, 0));
+            let element = Element::byte(
+                text,
+                x as i32,
+                y as i32,
+                color_scheme.color(*byte, &self.theme),
+            );
             elements.push_back(element);
         }
 
         for (i, byte) in bytes.iter().enumerate() {
-            let line = i / 16;
-            let index = i % 16;
+            let line = i / self.bytes_per_line;
+            let index = i % self.bytes_per_line;
             let text = {
                 let c = match char::from_u32(*byte as u32) {
                     Some(c) => {
@@ -301,7 +388,12 @@ impl Geometry {
             let x = self.char_view_start + index as u32 * self.char_width;
             let y = line as u32 * self.char_height;
 
-            let element = Element::byte(text, x as i32, y as i32, (0, 0, 0));
+            let element = Element::byte(
+                text,
+                x as i32,
+                y as i32,
+                color_scheme.color(*byte, &self.theme),
+            );
             elements.push_back(element);
         }
         elements
@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::{Error, HitKind, Pattern, Result, SearchHit};
+
+/// Bytes of a GUID.
+pub const GUID_SIZE: usize = 16;
+
+/// Well known GUIDs of the UEFI specification and EDK2, one per line.
+const BUILTIN: &str = "\
+7A9354D9-0468-444A-81CE-0BF617D890DF gEfiFirmwareFileSystemGuid
+8C8CE578-8A3D-4F1C-9935-896185C32DD3 gEfiFirmwareFileSystem2Guid
+5473C07A-3DCB-4DCA-BD6F-1E9689E7349A gEfiFirmwareFileSystem3Guid
+FFF12B8D-7696-4C8B-A985-2747075B4F50 gEfiSystemNvDataFvGuid
+0F9D89E8-9259-4F76-A5AF-0C89E34023DF gEfiFirmwareContentsSignedGuid
+FC1BCDB0-7D31-49AA-936A-A4600D9DD083 gEfiCrc32GuidedSectionExtractionGuid
+EE4E5898-3914-4259-9D6E-DC7BD79403CF gLzmaCustomDecompressGuid
+A31280AD-481E-41B6-95E8-127F4C984779 gTianoCustomDecompressGuid
+8BE4DF61-93CA-11D2-AA0D-00E098032B8C gEfiGlobalVariableGuid
+D719B2CB-3D3A-4596-A3BC-DAD00E67656F gEfiImageSecurityDatabaseGuid
+DDCF3616-3275-4164-98B6-FE85707FFE7D gEfiVariableGuid
+AAF32C78-947B-439A-A180-2E144EC37792 gEfiAuthenticatedVariableGuid
+A5C059A1-94E4-4AA7-87B5-AB155C2BF072 gEfiCertX509Guid
+C1C41626-504C-4092-ACA9-41F936934328 gEfiCertSha256Guid
+4AAFD29D-68DF-49EE-8AA9-347D375665A7 gEfiCertPkcs7Guid
+A7717414-C616-4977-9420-844712A735BF gEfiCertTypeRsa2048Sha256Guid
+7739F24C-93D7-11D4-9A3A-0090273FC14D gEfiHobListGuid
+05AD34BA-6F02-4214-952E-4DA0398E2BB9 gEfiDxeServicesTableGuid
+4C19049F-4137-4DD3-9C10-8B97A83FFDFA gEfiMemoryTypeInformationGuid
+EB9D2D30-2D88-11D3-9A16-0090273FC14D gEfiAcpiTableGuid
+8868E871-E4F1-11D3-BC22-0080C73C8881 gEfiAcpi20TableGuid
+EB9D2D31-2D88-11D3-9A16-0090273FC14D gEfiSmbiosTableGuid
+F2FD1544-9794-4A2C-992E-E5BBCF20E394 gEfiSmbios3TableGuid
+7CE88FB3-4BD7-4679-87A8-A8D8DEE50D2B gEfiEventReadyToBootGuid
+27ABF055-B1B8-4C26-8048-748F37BAA2DF gEfiEventExitBootServicesGuid
+09576E92-6D3F-11D2-8E39-00A0C969723B gEfiFileInfoGuid
+09576E93-6D3F-11D2-8E39-00A0C969723B gEfiFileSystemInfoGuid
+C12A7328-F81F-11D2-BA4B-00A0C93EC93B gEfiPartTypeSystemPartGuid
+5B1B31A1-9562-11D2-8E3F-00A0C969723B gEfiLoadedImageProtocolGuid
+09576E91-6D3F-11D2-8E39-00A0C969723B gEfiDevicePathProtocolGuid
+56EC3091-954C-11D2-8E3F-00A0C969723B gEfiLoadFileProtocolGuid
+964E5B22-6459-11D2-8E39-00A0C969723B gEfiSimpleFileSystemProtocolGuid
+964E5B21-6459-11D2-8E39-00A0C969723B gEfiBlockIoProtocolGuid
+CE345171-BA0B-11D2-8E4F-00A0C969723B gEfiDiskIoProtocolGuid
+387477C1-69C7-11D2-8E39-00A0C969723B gEfiSimpleTextInProtocolGuid
+387477C2-69C7-11D2-8E39-00A0C969723B gEfiSimpleTextOutProtocolGuid
+9042A9DE-23DC-4A38-96FB-7ADED080516A gEfiGraphicsOutputProtocolGuid
+4CF5B200-68B8-4CA5-9EEC-B23E3F50029A gEfiPciIoProtocolGuid
+18A031AB-B443-4D1A-A5C0-0C09261E9F71 gEfiDriverBindingProtocolGuid
+6A7A5CFF-E8D9-4F70-BADA-75AB3025CE14 gEfiComponentName2ProtocolGuid
+220E73B6-6BDB-4413-8405-B974B108619A gEfiFirmwareVolume2ProtocolGuid
+8F644FA9-E850-4DB1-9CE2-0B44698E8DA4 gEfiFirmwareVolumeBlockProtocolGuid
+F4CCBFB7-F6E0-47FD-9DD4-10A8F150C191 gEfiSmmBase2ProtocolGuid
+F894643D-C449-42D1-8EA8-85BDD8C65BDE gEfiPeiMemoryDiscoveredPpiGuid
+49EDB1C1-BF21-4761-BB12-EB0031AABB39 gEfiPeiFirmwareVolumeInfoPpiGuid
+";
+
+/// A GUID in its in-memory byte order, the first three fields little
+/// endian as UEFI stores them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Guid(pub [u8; GUID_SIZE]);
+
+impl Guid {
+    /// The GUID at the start of `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(Self(bytes.get(..GUID_SIZE)?.try_into().ok()?))
+    }
+
+    /// Parses the C initializer of EDK2 sources and `.dec` files, like
+    /// `{ 0x8c8ce578, 0x8a3d, 0x4f1c, { 0x99, 0x35, ... }}`.
+    fn from_initializer(text: &str) -> Result<Self> {
+        let fields: Vec<&str> = text
+            .split(|c: char| c == ',' || c == '{' || c == '}' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        if fields.len() != 11 {
+            anyhow::bail!("expected 11 fields in a GUID initializer");
+        }
+        let mut bytes = Vec::with_capacity(GUID_SIZE);
+        for (index, field) in fields.iter().enumerate() {
+            let digits = field
+                .strip_prefix("0x")
+                .or_else(|| field.strip_prefix("0X"))
+                .unwrap_or(field);
+            let value = u32::from_str_radix(digits, 16)?;
+            match index {
+                0 => bytes.extend_from_slice(&value.to_le_bytes()),
+                1 | 2 => bytes.extend_from_slice(&u16::try_from(value)?.to_le_bytes()),
+                _ => bytes.push(u8::try_from(value)?),
+            }
+        }
+        Ok(Self(bytes.try_into().unwrap()))
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = &self.0;
+        write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-",
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            u16::from_le_bytes([bytes[4], bytes[5]]),
+            u16::from_le_bytes([bytes[6], bytes[7]]),
+            bytes[8],
+            bytes[9]
+        )?;
+        bytes[10..]
+            .iter()
+            .try_for_each(|byte| write!(f, "{:02X}", byte))
+    }
+}
+
+impl FromStr for Guid {
+    type Err = Error;
+
+    /// Parses `8C8CE578-8A3D-4F1C-9935-896185C32DD3`, optionally in braces,
+    /// or a C initializer.
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.contains(',') {
+            return Self::from_initializer(text);
+        }
+        let hex: String = text
+            .trim_matches(|c| c == '{' || c == '}')
+            .chars()
+            .filter(|c| *c != '-')
+            .collect();
+        if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("expected a GUID like 8C8CE578-8A3D-4F1C-9935-896185C32DD3");
+        }
+        let mut bytes = [0; GUID_SIZE];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16)?;
+        }
+        bytes[0..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+        Ok(Self(bytes))
+    }
+}
+
+/// Names of known GUIDs, searchable as a [`Pattern`] labelling each hit
+/// with the name.
+#[derive(Clone, Debug)]
+pub struct GuidDatabase {
+    names: HashMap<Guid, String>,
+    /// Whether a known GUID starts with the two bytes at this index.
+    prefixes: Vec<bool>,
+}
+
+#[derive(Deserialize)]
+struct GuidFile {
+    /// `.dec` files to import, relative to the GUID file.
+    #[serde(default)]
+    dec: Vec<String>,
+    #[serde(default)]
+    guids: BTreeMap<String, String>,
+}
+
+impl GuidDatabase {
+    pub fn new() -> Self {
+        Self {
+            names: HashMap::new(),
+            prefixes: vec![false; 1 << 16],
+        }
+    }
+
+    /// GUIDs of the UEFI specification and EDK2 base packages.
+    pub fn builtin() -> Self {
+        let mut database = Self::new();
+        for line in BUILTIN.lines() {
+            let (guid, name) = line.split_once(' ').expect("GUID and name");
+            database.insert(name, guid.parse().expect("valid built-in GUID"));
+        }
+        database
+    }
+
+    /// Names the GUID, replacing an earlier name.
+    pub fn insert(&mut self, name: &str, guid: Guid) {
+        self.prefixes[prefix(&guid.0)] = true;
+        self.names.insert(guid, name.into());
+    }
+
+    /// Adds all names of `other`, returning how many there were.
+    pub fn merge(&mut self, other: GuidDatabase) -> usize {
+        let count = other.len();
+        for (guid, name) in other.names {
+            self.insert(&name, guid);
+        }
+        count
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, guid: &Guid) -> Option<&str> {
+        self.names.get(guid).map(String::as_str)
+    }
+
+    /// The GUID named exactly `name`.
+    pub fn guid(&self, name: &str) -> Option<Guid> {
+        self.names
+            .iter()
+            .find(|(_, candidate)| *candidate == name)
+            .map(|(guid, _)| *guid)
+    }
+
+    /// Name of the GUID at the start of `bytes`.
+    pub fn name_at(&self, bytes: &[u8]) -> Option<&str> {
+        if bytes.len() < GUID_SIZE || !self.prefixes.get(prefix(bytes)).copied()? {
+            return None;
+        }
+        self.name(&Guid::from_bytes(bytes)?)
+    }
+
+    /// The GUIDs whose name contains `query` ignoring case, or the GUID
+    /// `query` itself. An empty query keeps all of them.
+    pub fn matching(&self, query: &str) -> GuidDatabase {
+        let query = query.trim();
+        let mut database = Self::new();
+        if let Ok(guid) = query.parse::<Guid>() {
+            let name = self.name(&guid).unwrap_or(query);
+            database.insert(name, guid);
+            return database;
+        }
+        let query = query.to_lowercase();
+        for (guid, name) in &self.names {
+            if name.to_lowercase().contains(&query) {
+                database.insert(name, *guid);
+            }
+        }
+        database
+    }
+
+    /// Reads the `[Guids]`, `[Protocols]` and `[Ppis]` style entries
+    /// `gName = { 0x..., ... }` of an EDK2 package declaration.
+    pub fn parse_dec(text: &str) -> Self {
+        let mut database = Self::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => continue,
+            };
+            let valid_name =
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_name || !value.starts_with('{') {
+                continue;
+            }
+            if let Ok(guid) = Guid::from_initializer(value) {
+                database.insert(name, guid);
+            }
+        }
+        database
+    }
+
+    pub fn load_dec(path: &Path) -> Result<Self> {
+        Ok(Self::parse_dec(&fs::read_to_string(path)?))
+    }
+
+    /// Loads a user file naming GUIDs and listing `.dec` files to import:
+    ///
+    /// ```toml
+    /// dec = ["edk2/MdePkg/MdePkg.dec"]
+    ///
+    /// [guids]
+    /// gMyPlatformGuid = "01234567-89AB-CDEF-0123-456789ABCDEF"
+    /// ```
+    pub fn load(path: &Path) -> Result<Self> {
+        let file: GuidFile = toml::from_str(&fs::read_to_string(path)?)?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut database = Self::new();
+        for dec in &file.dec {
+            let dec = directory.join(dec);
+            let imported = Self::load_dec(&dec)
+                .map_err(|err| anyhow::anyhow!("{}: {}", dec.display(), err))?;
+            database.merge(imported);
+        }
+        for (name, guid) in &file.guids {
+            let guid = guid
+                .parse()
+                .map_err(|err| anyhow::anyhow!("GUID {}: {}", name, err))?;
+            database.insert(name, guid);
+        }
+        Ok(database)
+    }
+}
+
+impl Default for GuidDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pattern for GuidDatabase {
+    fn overlap(&self) -> usize {
+        GUID_SIZE
+    }
+
+    fn find_in(
+        &self,
+        bytes: &[u8],
+        limit: usize,
+        base: usize,
+        _last: bool,
+        hits: &mut Vec<SearchHit>,
+    ) {
+        let end = std::cmp::min(limit, (bytes.len() + 1).saturating_sub(GUID_SIZE));
+        for start in 0..end {
+            if let Some(name) = self.name_at(&bytes[start..]) {
+                hits.push(SearchHit {
+                    range: base + start..base + start + GUID_SIZE,
+                    label: name.into(),
+                    kind: HitKind::Guid,
+                    captures: Vec::new(),
+                });
+            }
+        }
+    }
+}
+
+fn prefix(bytes: &[u8]) -> usize {
+    u16::from_le_bytes([bytes[0], bytes[1]]) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_endian_round_trip() {
+        let text = "8C8CE578-8A3D-4F1C-9935-896185C32DD3";
+        let guid: Guid = text.parse().unwrap();
+        assert_eq!(
+            guid.0,
+            [
+                0x78, 0xE5, 0x8C, 0x8C, 0x3D, 0x8A, 0x1C, 0x4F, 0x99, 0x35, 0x89, 0x61, 0x85, 0xC3,
+                0x2D, 0xD3
+            ]
+        );
+        assert_eq!(guid.to_string(), text);
+        assert_eq!(
+            format!("{{{}}}", text.to_lowercase())
+                .parse::<Guid>()
+                .unwrap(),
+            guid
+        );
+        let initializer =
+            "{ 0x8c8ce578, 0x8a3d, 0x4f1c, { 0x99, 0x35, 0x89, 0x61, 0x85, 0xc3, 0x2d, 0xd3 }}";
+        assert_eq!(initializer.parse::<Guid>().unwrap(), guid);
+        assert!("8C8CE578-8A3D-4F1C-9935-896185C32DD"
+            .parse::<Guid>()
+            .is_err());
+    }
+
+    #[test]
+    fn dec_entries() {
+        let dec = "\
+[Guids]
+  ## Include/Guid/GlobalVariable.h
+  gEfiGlobalVariableGuid         = { 0x8BE4DF61, 0x93CA, 0x11D2, { 0xAA, 0x0D, 0x00, 0xE0, 0x98, 0x03, 0x2B, 0x8C }}
+  gEfiMdePkgTokenSpaceGuid.PcdMaximumUnicodeStringLength|1000000|UINT32|0x00000001
+";
+        let database = GuidDatabase::parse_dec(dec);
+        assert_eq!(database.len(), 1);
+        let guid: Guid = "8BE4DF61-93CA-11D2-AA0D-00E098032B8C".parse().unwrap();
+        assert_eq!(database.name(&guid), Some("gEfiGlobalVariableGuid"));
+        assert_eq!(database.name_at(&guid.0), Some("gEfiGlobalVariableGuid"));
+    }
+
+    #[test]
+    fn default_database() {
+        let mut database = GuidDatabase::default();
+        let guid: Guid = "8BE4DF61-93CA-11D2-AA0D-00E098032B8C".parse().unwrap();
+        assert_eq!(database.name_at(&guid.0), None);
+        database.insert("gEfiGlobalVariableGuid", guid);
+        assert_eq!(database.name_at(&guid.0), Some("gEfiGlobalVariableGuid"));
+    }
+}
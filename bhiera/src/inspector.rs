@@ -0,0 +1,583 @@
+use std::fmt::{Display, LowerExp};
+
+use crate::endian::{read_uint, sign_extend, write_uint};
+use crate::{Endian, Guid, Result};
+
+/// The ways the data inspector decodes bytes at the cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    Int {
+        size: usize,
+        signed: bool,
+        endian: Endian,
+    },
+    Float {
+        size: usize,
+        endian: Endian,
+    },
+    /// UEFI layout, the first three fields are little endian.
+    Guid,
+    /// FAT time in the low and date in the high little endian word.
+    DosTime,
+    UnixTime32,
+    UnixTime64,
+    /// 100 ns intervals since 1601-01-01.
+    FileTime,
+    Uleb128,
+    Sleb128,
+    Bits,
+}
+
+impl ValueKind {
+    pub fn all() -> Vec<ValueKind> {
+        let mut kinds = vec![
+            ValueKind::Int {
+                size: 1,
+                signed: true,
+                endian: Endian::Little,
+            },
+            ValueKind::Int {
+                size: 1,
+                signed: false,
+                endian: Endian::Little,
+            },
+        ];
+        for size in [2, 4, 8] {
+            for signed in [true, false] {
+                for endian in [Endian::Little, Endian::Big] {
+                    kinds.push(ValueKind::Int {
+                        size,
+                        signed,
+                        endian,
+                    });
+                }
+            }
+        }
+        for size in [2, 4, 8] {
+            for endian in [Endian::Little, Endian::Big] {
+                kinds.push(ValueKind::Float { size, endian });
+            }
+        }
+        kinds.extend([
+            ValueKind::Guid,
+            ValueKind::DosTime,
+            ValueKind::UnixTime32,
+            ValueKind::UnixTime64,
+            ValueKind::FileTime,
+            ValueKind::Uleb128,
+            ValueKind::Sleb128,
+            ValueKind::Bits,
+        ]);
+        kinds
+    }
+
+    pub fn name(&self) -> String {
+        let endian = |endian: &Endian| match endian {
+            Endian::Little => "LE",
+            Endian::Big => "BE",
+        };
+        match self {
+            ValueKind::Int {
+                size: 1, signed, ..
+            } => format!("{}8", if *signed { "i" } else { "u" }),
+            ValueKind::Int {
+                size,
+                signed,
+                endian: e,
+            } => format!(
+                "{}{} {}",
+                if *signed { "i" } else { "u" },
+                size * 8,
+                endian(e)
+            ),
+            ValueKind::Float { size, endian: e } => format!("f{} {}", size * 8, endian(e)),
+            ValueKind::Guid => "GUID".into(),
+            ValueKind::DosTime => "DOS date/time".into(),
+            ValueKind::UnixTime32 => "Unix time 32".into(),
+            ValueKind::UnixTime64 => "Unix time 64".into(),
+            ValueKind::FileTime => "FILETIME".into(),
+            ValueKind::Uleb128 => "ULEB128".into(),
+            ValueKind::Sleb128 => "SLEB128".into(),
+            ValueKind::Bits => "Bits".into(),
+        }
+    }
+
+    /// Number of bytes the value occupies at the start of `bytes`.
+    pub fn size(&self, bytes: &[u8]) -> Option<usize> {
+        let size = match self {
+            ValueKind::Int { size, .. } | ValueKind::Float { size, .. } => *size,
+            ValueKind::Guid => 16,
+            ValueKind::DosTime | ValueKind::UnixTime32 => 4,
+            ValueKind::UnixTime64 | ValueKind::FileTime => 8,
+            ValueKind::Uleb128 | ValueKind::Sleb128 => {
+                bytes.iter().take(10).position(|byte| byte & 0x80 == 0)? + 1
+            }
+            ValueKind::Bits => 1,
+        };
+        (bytes.len() >= size).then_some(size)
+    }
+
+    /// Decodes the value at the start of `bytes`, `None` if there are not
+    /// enough bytes or they are no valid value of this kind.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        let bytes = &bytes[..self.size(bytes)?];
+        let text = match self {
+            ValueKind::Int {
+                size,
+                signed,
+                endian,
+            } => {
+                let value = read_uint(bytes, *endian);
+                match signed {
+                    true => sign_extend(value, *size).to_string(),
+                    false => value.to_string(),
+                }
+            }
+            ValueKind::Float { size: 2, endian } => {
+                format_float(half::f16::from_bits(read_uint(bytes, *endian) as u16))
+            }
+            ValueKind::Float { size: 4, endian } => {
+                format_float(f32::from_bits(read_uint(bytes, *endian) as u32))
+            }
+            ValueKind::Float { endian, .. } => {
+                format_float(f64::from_bits(read_uint(bytes, *endian)))
+            }
+            ValueKind::Guid => Guid::from_bytes(bytes)?.to_string(),
+            ValueKind::DosTime => {
+                let value = read_uint(bytes, Endian::Little);
+                let (time, date) = (value & 0xFFFF, value >> 16);
+                let (year, month, day) = (1980 + (date >> 9), (date >> 5) & 0xF, date & 0x1F);
+                let (hour, minute, second) = (time >> 11, (time >> 5) & 0x3F, (time & 0x1F) * 2);
+                // Two second steps go up to 62.
+                let valid = (1..=12).contains(&month)
+                    && (1..=days_in_month(year as i64, month as i64)).contains(&(day as i64))
+                    && hour < 24
+                    && minute < 60
+                    && second < 60;
+                if !valid {
+                    return None;
+                }
+                format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                    year, month, day, hour, minute, second
+                )
+            }
+            ValueKind::UnixTime32 => {
+                format_timestamp(read_uint(bytes, Endian::Little) as u32 as i64)
+            }
+            ValueKind::UnixTime64 => format_timestamp(read_uint(bytes, Endian::Little) as i64),
+            ValueKind::FileTime => {
+                let ticks = read_uint(bytes, Endian::Little);
+                format_timestamp((ticks / 10_000_000) as i64 - FILETIME_UNIX_OFFSET)
+            }
+            ValueKind::Uleb128 => read_leb128(bytes).to_string(),
+            ValueKind::Sleb128 => {
+                let bits = std::cmp::min(bytes.len() * 7, 64);
+                let value = read_leb128(bytes);
+                match bits < 64 && value & (1 << (bits - 1)) != 0 {
+                    true => (value as i64 | (-1i64 << bits)).to_string(),
+                    false => (value as i64).to_string(),
+                }
+            }
+            ValueKind::Bits => format!("{:08b}", bytes[0]),
+        };
+        Some(text)
+    }
+
+    /// Encodes `text` as this kind, the inverse of `decode`.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let text = text.trim();
+        let bytes = match self {
+            ValueKind::Int {
+                size,
+                signed,
+                endian,
+            } => {
+                let value = parse_int(text)?;
+                let bits = (*size * 8) as u32;
+                let (min, max) = match signed {
+                    true => (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1),
+                    false => (0, (1i128 << bits) - 1),
+                };
+                if value < min || value > max {
+                    anyhow::bail!("{} does not fit into {}", value, self.name());
+                }
+                write_uint(value as u64, *size, *endian)
+            }
+            ValueKind::Float { size: 2, endian } => write_uint(
+                half::f16::from_f32(text.parse()?).to_bits() as u64,
+                2,
+                *endian,
+            ),
+            ValueKind::Float { size: 4, endian } => {
+                write_uint(text.parse::<f32>()?.to_bits() as u64, 4, *endian)
+            }
+            ValueKind::Float { endian, .. } => {
+                write_uint(text.parse::<f64>()?.to_bits(), 8, *endian)
+            }
+            ValueKind::Guid => text.parse::<Guid>()?.0.to_vec(),
+            ValueKind::DosTime => {
+                let (year, month, day, hour, minute, second) = parse_timestamp(text)?;
+                if !(1980..=2107).contains(&year) {
+                    anyhow::bail!("DOS dates range from 1980 to 2107");
+                }
+                let date = (((year - 1980) << 9) | (month << 5) | day) as u64;
+                let time = ((hour << 11) | (minute << 5) | (second / 2)) as u64;
+                write_uint((date << 16) | time, 4, Endian::Little)
+            }
+            ValueKind::UnixTime32 => {
+                let seconds = timestamp_seconds(text)?;
+                let seconds =
+                    u32::try_from(seconds).map_err(|_| anyhow::anyhow!("out of range"))?;
+                write_uint(seconds as u64, 4, Endian::Little)
+            }
+            ValueKind::UnixTime64 => write_uint(timestamp_seconds(text)? as u64, 8, Endian::Little),
+            ValueKind::FileTime => {
+                let seconds = timestamp_seconds(text)?
+                    .checked_add(FILETIME_UNIX_OFFSET)
+                    .ok_or_else(|| anyhow::anyhow!("out of range"))?;
+                let ticks = u64::try_from(seconds)
+                    .map_err(|_| anyhow::anyhow!("before 1601"))?
+                    .checked_mul(10_000_000)
+                    .ok_or_else(|| anyhow::anyhow!("out of range"))?;
+                write_uint(ticks, 8, Endian::Little)
+            }
+            ValueKind::Uleb128 => {
+                let value = parse_int(text)?;
+                let value = u64::try_from(value).map_err(|_| anyhow::anyhow!("out of range"))?;
+                write_leb128(value as i128, false)
+            }
+            ValueKind::Sleb128 => {
+                let value = parse_int(text)?;
+                let value = i64::try_from(value).map_err(|_| anyhow::anyhow!("out of range"))?;
+                write_leb128(value as i128, true)
+            }
+            ValueKind::Bits => {
+                if text.len() != 8 {
+                    anyhow::bail!("expected 8 binary digits");
+                }
+                vec![u8::from_str_radix(text, 2)?]
+            }
+        };
+        Ok(bytes)
+    }
+
+    /// Encodes `text` to overwrite a value of `size` bytes. LEB128 values
+    /// are padded with continuation bytes to keep the length.
+    pub fn encode_sized(&self, text: &str, size: usize) -> Result<Vec<u8>> {
+        let mut bytes = self.encode(text)?;
+        let leb128 = matches!(self, ValueKind::Uleb128 | ValueKind::Sleb128);
+        if bytes.len() > size || (bytes.len() < size && !leb128) {
+            anyhow::bail!(
+                "{} takes {} bytes instead of {}",
+                text.trim(),
+                bytes.len(),
+                size
+            );
+        }
+        let fill = match bytes.last() {
+            Some(byte) if *self == ValueKind::Sleb128 && byte & 0x40 != 0 => 0x7F,
+            _ => 0x00,
+        };
+        while bytes.len() < size {
+            *bytes.last_mut().unwrap() |= 0x80;
+            bytes.push(fill);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Seconds between 1601-01-01 and 1970-01-01.
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+/// Switches to exponent notation for very large and small magnitudes.
+fn format_float<T>(value: T) -> String
+where
+    T: Copy + Into<f64> + Display + LowerExp,
+{
+    let magnitude = value.into().abs();
+    match magnitude != 0.0 && !(1e-6..1e16).contains(&magnitude) {
+        true => format!("{:e}", value),
+        false => value.to_string(),
+    }
+}
+
+fn read_leb128(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .enumerate()
+        .take(10)
+        .fold(0, |value, (index, byte)| {
+            value
+                | ((byte & 0x7F) as u64)
+                    .checked_shl(index as u32 * 7)
+                    .unwrap_or(0)
+        })
+}
+
+fn write_leb128(mut value: i128, signed: bool) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let done = match signed {
+            true => (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0),
+            false => value == 0,
+        };
+        if done {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn parse_int(text: &str) -> Result<i128> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16)?,
+        None => digits.parse()?,
+    };
+    Ok(if negative { -value } else { value })
+}
+
+/// Formats seconds since the Unix epoch as UTC.
+fn format_timestamp(seconds: i64) -> String {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+fn parse_timestamp(text: &str) -> Result<(i64, i64, i64, i64, i64, i64)> {
+    // Years before 1 BC are negative.
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(text) => (-1, text),
+        None => (1, text),
+    };
+    let fields: Vec<i64> = text
+        .split(['-', ':', ' ', 'T'])
+        .filter(|field| !field.is_empty())
+        .map(|field| field.parse())
+        .collect::<std::result::Result<_, _>>()?;
+    match fields[..] {
+        [year, month, day, hour, minute, second]
+            if (1..=12).contains(&month)
+                && (1..=days_in_month(sign * year, month)).contains(&day)
+                && hour < 24
+                && minute < 60
+                && second < 60 =>
+        {
+            Ok((sign * year, month, day, hour, minute, second))
+        }
+        _ => anyhow::bail!("expected YYYY-MM-DD HH:MM:SS"),
+    }
+}
+
+fn timestamp_seconds(text: &str) -> Result<i64> {
+    let (year, month, day, hour, minute, second) = parse_timestamp(text)?;
+    let (hour, minute, second) = (hour as i128, minute as i128, second as i128);
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    i64::try_from(seconds).map_err(|_| anyhow::anyhow!("out of range"))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Howard Hinnant's algorithms for the proleptic Gregorian calendar.
+// Computed in i128 so that no year overflows.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i128 {
+    let (year, month, day) = (year as i128, month as i128, day as i128);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(size: usize, signed: bool, endian: Endian) -> ValueKind {
+        ValueKind::Int {
+            size,
+            signed,
+            endian,
+        }
+    }
+
+    fn decode(kind: ValueKind, bytes: &[u8]) -> String {
+        kind.decode(bytes).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let samples: [&[u8]; 3] = [
+            &[0x00, 0x3C, 0x21, 0x00, 0xE5, 0x8E, 0x26, 0x00, 0x01],
+            &[0xFF, 0xFE, 0xFD, 0x80, 0x7F, 0x10, 0x20, 0x30, 0x40],
+            &[0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00],
+        ];
+        for kind in ValueKind::all() {
+            // Both have a finer resolution than the text shows.
+            if matches!(kind, ValueKind::FileTime | ValueKind::DosTime) {
+                continue;
+            }
+            for bytes in samples {
+                let bytes = [bytes, &[0; 16]].concat();
+                let size = kind.size(&bytes).unwrap();
+                // NaN payloads are not shown.
+                if let Some(text) = kind.decode(&bytes).filter(|text| text != "NaN") {
+                    assert_eq!(
+                        kind.encode_sized(&text, size).unwrap(),
+                        &bytes[..size],
+                        "{}",
+                        kind.name()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sign_extension() {
+        assert_eq!(decode(int(1, true, Endian::Little), &[0xFF]), "-1");
+        assert_eq!(decode(int(1, false, Endian::Little), &[0xFF]), "255");
+        assert_eq!(decode(int(2, true, Endian::Big), &[0xFF, 0xFE]), "-2");
+        assert_eq!(
+            decode(int(4, true, Endian::Little), &[0, 0, 0, 0x80]),
+            "-2147483648"
+        );
+        assert_eq!(int(1, true, Endian::Little).encode("-128").unwrap(), [0x80]);
+        assert!(int(1, true, Endian::Little).encode("-129").is_err());
+        assert!(int(1, false, Endian::Little).encode("256").is_err());
+        assert!(int(2, false, Endian::Little).encode("-1").is_err());
+        assert_eq!(
+            int(2, true, Endian::Big).encode("0x7FFF").unwrap(),
+            [0x7F, 0xFF]
+        );
+    }
+
+    #[test]
+    fn half_float() {
+        let kind = ValueKind::Float {
+            size: 2,
+            endian: Endian::Little,
+        };
+        assert_eq!(decode(kind, &[0x00, 0x3C]), "1");
+        assert_eq!(decode(kind, &[0x00, 0xC0]), "-2");
+        assert_eq!(kind.encode("65504").unwrap(), [0xFF, 0x7B]);
+        assert_eq!(kind.encode("1").unwrap(), [0x00, 0x3C]);
+        assert_eq!(decode(kind, &[0x00, 0x7C]), "inf");
+    }
+
+    #[test]
+    fn file_time() {
+        let kind = ValueKind::FileTime;
+        assert_eq!(decode(kind, &[0; 8]), "1601-01-01 00:00:00");
+        let epoch = 116_444_736_000_000_000u64.to_le_bytes();
+        assert_eq!(decode(kind, &epoch), "1970-01-01 00:00:00");
+        assert_eq!(kind.encode("1970-01-01 00:00:00").unwrap(), epoch);
+        assert_eq!(kind.encode("1601-01-01 00:00:00").unwrap(), [0; 8]);
+        assert!(kind.encode("1600-12-31 23:59:59").is_err());
+        assert!(kind.encode("70000-01-01 00:00:00").is_err());
+    }
+
+    #[test]
+    fn dos_time() {
+        let kind = ValueKind::DosTime;
+        assert_eq!(decode(kind, &[0, 0, 0x21, 0]), "1980-01-01 00:00:00");
+        assert!(kind.decode(&[0, 0, 0, 0]).is_none());
+        assert_eq!(kind.encode("1980-01-01 00:00:00").unwrap(), [0, 0, 0x21, 0]);
+        let last = kind.encode("2107-12-31 23:59:58").unwrap();
+        assert_eq!(decode(kind, &last), "2107-12-31 23:59:58");
+        assert!(kind.encode("1979-12-31 23:59:59").is_err());
+        assert!(kind.encode("2108-01-01 00:00:00").is_err());
+        // 62 seconds, February 30 and April 31 are not shown.
+        assert!(kind.decode(&[0x1F, 0, 0x21, 0]).is_none());
+        assert!(kind.decode(&[0, 0, 0x5E, 0]).is_none());
+        assert!(kind.decode(&[0, 0, 0x9F, 0]).is_none());
+        assert_eq!(decode(kind, &[0, 0, 0x5D, 0x08]), "1984-02-29 00:00:00");
+        assert!(kind.decode(&[0, 0, 0x5D, 0x0A]).is_none());
+        assert!(kind.encode("1985-02-29 00:00:00").is_err());
+        assert!(ValueKind::UnixTime32.encode("2023-04-31 00:00:00").is_err());
+    }
+
+    #[test]
+    fn huge_years() {
+        for kind in [
+            ValueKind::UnixTime32,
+            ValueKind::UnixTime64,
+            ValueKind::FileTime,
+        ] {
+            assert!(kind.encode("9223372036854775807-12-31 23:59:59").is_err());
+            assert!(kind.encode("99999999999999999999-01-01 00:00:00").is_err());
+        }
+        assert!(ValueKind::UnixTime32.encode("2106-02-07 06:28:16").is_err());
+        assert_eq!(
+            ValueKind::UnixTime32.encode("2106-02-07 06:28:15").unwrap(),
+            [0xFF; 4]
+        );
+    }
+
+    #[test]
+    fn leb128() {
+        assert_eq!(decode(ValueKind::Uleb128, &[0xE5, 0x8E, 0x26]), "624485");
+        assert_eq!(decode(ValueKind::Sleb128, &[0xC0, 0xBB, 0x78]), "-123456");
+        assert_eq!(
+            ValueKind::Uleb128.encode("624485").unwrap(),
+            [0xE5, 0x8E, 0x26]
+        );
+        assert_eq!(
+            ValueKind::Sleb128.encode("-123456").unwrap(),
+            [0xC0, 0xBB, 0x78]
+        );
+        let padded = ValueKind::Uleb128.encode_sized("1", 3).unwrap();
+        assert_eq!(padded, [0x81, 0x80, 0x00]);
+        assert_eq!(decode(ValueKind::Uleb128, &padded), "1");
+        let padded = ValueKind::Sleb128.encode_sized("-1", 3).unwrap();
+        assert_eq!(padded, [0xFF, 0xFF, 0x7F]);
+        assert_eq!(decode(ValueKind::Sleb128, &padded), "-1");
+        let padded = ValueKind::Sleb128.encode_sized("63", 2).unwrap();
+        assert_eq!(decode(ValueKind::Sleb128, &padded), "63");
+        assert!(ValueKind::Uleb128.encode_sized("624485", 2).is_err());
+        assert!(int(2, false, Endian::Little).encode_sized("1", 4).is_err());
+    }
+}
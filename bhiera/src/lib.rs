@@ -1,15 +1,64 @@
+mod address;
 mod bhiera;
+mod byte_class;
+mod checksum;
+mod chunks;
+mod cursor;
 mod data_provider;
+mod disassembly;
+mod edit;
+mod edited_data_provider;
 mod element;
+mod endian;
 mod error;
+mod expression;
 mod file_data_provider;
 mod geometry;
+mod guid;
+mod inspector;
+mod memory_data_provider;
+mod navigation;
+mod overview;
+mod pixels;
+mod search;
+mod sidecar;
+mod signature;
+mod stats;
+mod stride;
+mod strings;
+mod theme;
 mod view;
 
+pub use address::AddressMode;
 pub use bhiera::{Bhiera, Model};
+pub use byte_class::{ByteClass, ColorScheme};
+pub use checksum::{checksum_matches, checksums, ChecksumKind};
+pub use cursor::Movement;
 pub use data_provider::DataProvider;
+pub use disassembly::{disassemble, Bitness, DecodedInstruction};
+pub use edit::Edit;
+pub use edited_data_provider::EditedDataProvider;
 pub use element::Element;
+pub use endian::Endian;
 pub use error::{Error, Result};
+pub use expression::Evaluator;
 pub use file_data_provider::FileDataProvider;
 pub use geometry::Geometry;
+pub use guid::{Guid, GuidDatabase, GUID_SIZE};
+pub use inspector::ValueKind;
+pub use memory_data_provider::MemoryDataProvider;
+pub use overview::{overview, OverviewMode, OverviewRow};
+pub use pixels::{PixelFormat, PixelLayout};
+pub use search::{
+    escape_bytes, find_all, replacement_bytes, BytePattern, HitKind, Pattern, RegexPattern,
+    SearchHit, TextEncoding, TextPattern,
+};
+pub use sidecar::{
+    content_hash, export_annotations, import_annotations, Annotation, Bookmark, Notes, Sidecar,
+};
+pub use signature::{Signature, SignatureSet};
+pub use stats::{histogram, EntropyEdge, EntropyProfile, Histogram};
+pub use stride::{find_strides, Stride};
+pub use strings::{find_strings, FoundString, StringEncoding};
+pub use theme::Theme;
 pub use view::View;
@@ -0,0 +1,29 @@
+use std::borrow::Cow;
+
+use crate::DataProvider;
+
+/// Document bytes held in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryDataProvider {
+    bytes: Vec<u8>,
+}
+
+impl MemoryDataProvider {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+}
+
+impl DataProvider for MemoryDataProvider {
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn get(&self, offset: usize, count: usize) -> Option<Cow<'_, [u8]>> {
+        if offset >= self.len() {
+            return None;
+        }
+        let end = std::cmp::min(offset.saturating_add(count), self.len());
+        self.bytes.get(offset..end).map(Cow::Borrowed)
+    }
+}
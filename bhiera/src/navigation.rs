@@ -0,0 +1,105 @@
+use std::ops::Range;
+
+const MAX_ENTRIES: usize = 256;
+
+/// Browser-like back and forward stacks of selections.
+#[derive(Clone, Debug, Default)]
+pub struct Navigation {
+    back: Vec<Range<usize>>,
+    forward: Vec<Range<usize>>,
+}
+
+impl Navigation {
+    /// Remembers `from` before a jump, which discards the forward stack.
+    pub fn record(&mut self, from: Range<usize>) {
+        if self.back.last() != Some(&from) {
+            self.back.push(from);
+        }
+        if self.back.len() > MAX_ENTRIES {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    pub fn back(&mut self, current: Range<usize>) -> Option<Range<usize>> {
+        let target = self.back.pop()?;
+        self.forward.push(current);
+        Some(target)
+    }
+
+    pub fn forward(&mut self, current: Range<usize>) -> Option<Range<usize>> {
+        let target = self.forward.pop()?;
+        self.back.push(current);
+        Some(target)
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut Range<usize>> {
+        self.back.iter_mut().chain(self.forward.iter_mut())
+    }
+
+    pub fn clear(&mut self) {
+        self.back.clear();
+        self.forward.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_and_forward() {
+        let mut navigation = Navigation::default();
+        assert_eq!(navigation.back(0..1), None);
+        navigation.record(0..1);
+        navigation.record(10..12);
+        assert_eq!(navigation.back(20..20), Some(10..12));
+        assert_eq!(navigation.back(10..12), Some(0..1));
+        assert_eq!(navigation.back(0..1), None);
+        assert_eq!(navigation.forward(0..1), Some(10..12));
+        assert_eq!(navigation.forward(10..12), Some(20..20));
+        assert_eq!(navigation.forward(20..20), None);
+        assert_eq!(navigation.back(20..20), Some(10..12));
+    }
+
+    #[test]
+    fn record_discards_forward() {
+        let mut navigation = Navigation::default();
+        navigation.record(0..1);
+        assert_eq!(navigation.back(5..5), Some(0..1));
+        navigation.record(0..1);
+        assert_eq!(navigation.forward(7..7), None);
+        assert_eq!(navigation.back(7..7), Some(0..1));
+    }
+
+    #[test]
+    fn repeated_entries() {
+        let mut navigation = Navigation::default();
+        navigation.record(3..4);
+        navigation.record(3..4);
+        assert_eq!(navigation.back(5..5), Some(3..4));
+        assert_eq!(navigation.back(3..4), None);
+    }
+
+    #[test]
+    fn entry_limit() {
+        let mut navigation = Navigation::default();
+        for i in 0..MAX_ENTRIES + 10 {
+            navigation.record(i..i);
+        }
+        assert_eq!(navigation.entries_mut().count(), MAX_ENTRIES);
+        assert_eq!(navigation.entries_mut().next(), Some(&mut (10..10)));
+    }
+
+    #[test]
+    fn clear() {
+        let mut navigation = Navigation::default();
+        navigation.record(0..1);
+        navigation.record(2..3);
+        navigation.back(4..4);
+        navigation.clear();
+        assert_eq!(navigation.entries_mut().count(), 0);
+        assert_eq!(navigation.back(4..4), None);
+        assert_eq!(navigation.forward(4..4), None);
+    }
+}
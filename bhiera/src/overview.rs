@@ -0,0 +1,155 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{ByteClass, ColorScheme, DataProvider, Error, Histogram, Theme};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverviewMode {
+    #[default]
+    ByteClass,
+    Entropy,
+}
+
+impl FromStr for OverviewMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "class" => Ok(OverviewMode::ByteClass),
+            "entropy" => Ok(OverviewMode::Entropy),
+            _ => Err(anyhow::anyhow!("unknown overview mode: {}", s)),
+        }
+    }
+}
+
+/// Summary of the bytes under one row of the overview bar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OverviewRow {
+    /// The most frequent byte class.
+    pub class: ByteClass,
+    /// Shannon entropy in bits per byte.
+    pub entropy: f64,
+}
+
+impl OverviewRow {
+    fn new(histogram: &Histogram) -> Self {
+        let mut classes: Vec<(ByteClass, u64)> = Vec::new();
+        for (byte, count) in histogram.counts().iter().enumerate() {
+            let class = ByteClass::of(byte as u8);
+            match classes.iter_mut().find(|(c, _)| *c == class) {
+                Some((_, total)) => *total += count,
+                None => classes.push((class, *count)),
+            }
+        }
+        let (class, _) = classes.into_iter().max_by_key(|(_, count)| *count).unwrap();
+        Self {
+            class,
+            entropy: histogram.entropy(),
+        }
+    }
+
+    pub fn color(&self, mode: OverviewMode, theme: &Theme) -> (u8, u8, u8) {
+        match mode {
+            OverviewMode::ByteClass => theme.class_color(self.class),
+            OverviewMode::Entropy => {
+                let level = (self.entropy / 8.0 * 255.0) as u8;
+                ColorScheme::Gradient.color(level, theme)
+            }
+        }
+    }
+}
+
+/// Downsamples the whole document into `rows` rows of the overview bar.
+/// `progress` is called with the number of rows done, the result is
+/// incomplete when `cancel` is set.
+pub fn overview<F>(
+    provider: &dyn DataProvider,
+    rows: usize,
+    cancel: &AtomicBool,
+    mut progress: F,
+) -> Vec<OverviewRow>
+where
+    F: FnMut(usize),
+{
+    let len = provider.len();
+    if len == 0 {
+        return Vec::new();
+    }
+    let mut overview = Vec::with_capacity(rows);
+    for row in 0..rows {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let begin = len * row / rows;
+        let end = std::cmp::max(len * (row + 1) / rows, begin + 1);
+        let bytes = provider.get(begin, end - begin).unwrap_or_default();
+        overview.push(OverviewRow::new(&Histogram::new(&bytes)));
+        progress(row + 1);
+    }
+    overview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn rows(bytes: &[u8], rows: usize) -> Vec<OverviewRow> {
+        let provider = MemoryDataProvider::new(bytes.to_vec());
+        overview(&provider, rows, &AtomicBool::new(false), |_| {})
+    }
+
+    #[test]
+    fn last_partial_row() {
+        // 10 bytes in 3 rows: the last row takes the 4 remaining bytes.
+        let overview = rows(b"\0\0\0   aaa\xff", 3);
+        let classes: Vec<ByteClass> = overview.iter().map(|row| row.class).collect();
+        assert_eq!(
+            classes,
+            [ByteClass::Null, ByteClass::Whitespace, ByteClass::Printable]
+        );
+        assert_eq!(overview[0].entropy, 0.0);
+        let entropy = -(0.75 * 0.75f64.log2() + 0.25 * 0.25f64.log2());
+        assert!((overview[2].entropy - entropy).abs() < 1e-9);
+    }
+
+    #[test]
+    fn last_byte_in_last_row() {
+        let overview = rows(b"\0\0\0\0\0\0\0\0\0\xff", 4);
+        assert_eq!(overview.len(), 4);
+        assert!(overview[..3].iter().all(|row| row.class == ByteClass::Null));
+        assert!(overview[3].entropy > 0.0);
+    }
+
+    #[test]
+    fn more_rows_than_bytes() {
+        let overview = rows(b"\0 a", 5);
+        let classes: Vec<ByteClass> = overview.iter().map(|row| row.class).collect();
+        assert_eq!(
+            classes,
+            [
+                ByteClass::Null,
+                ByteClass::Null,
+                ByteClass::Whitespace,
+                ByteClass::Whitespace,
+                ByteClass::Printable,
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_document() {
+        assert!(rows(b"", 4).is_empty());
+    }
+
+    #[test]
+    fn progress_and_cancel() {
+        let provider = MemoryDataProvider::new(vec![0; 100]);
+        let mut done = Vec::new();
+        let summary = overview(&provider, 4, &AtomicBool::new(false), |row| done.push(row));
+        assert_eq!(summary.len(), 4);
+        assert_eq!(done, [1, 2, 3, 4]);
+        let cancelled = overview(&provider, 4, &AtomicBool::new(true), |_| {});
+        assert!(cancelled.is_empty());
+    }
+}
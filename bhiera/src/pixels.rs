@@ -0,0 +1,334 @@
+use std::str::FromStr;
+
+use crate::{DataProvider, Error};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelFormat {
+    Mono1,
+    #[default]
+    Gray8,
+    Rgb565,
+    Rgb888,
+    Rgba8888,
+    Bgra8888,
+    Indexed8,
+}
+
+impl PixelFormat {
+    pub const ALL: [PixelFormat; 7] = [
+        PixelFormat::Gray8,
+        PixelFormat::Mono1,
+        PixelFormat::Rgb565,
+        PixelFormat::Rgb888,
+        PixelFormat::Rgba8888,
+        PixelFormat::Bgra8888,
+        PixelFormat::Indexed8,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PixelFormat::Mono1 => "1bpp",
+            PixelFormat::Gray8 => "gray8",
+            PixelFormat::Rgb565 => "rgb565",
+            PixelFormat::Rgb888 => "rgb888",
+            PixelFormat::Rgba8888 => "rgba8888",
+            PixelFormat::Bgra8888 => "bgra8888",
+            PixelFormat::Indexed8 => "indexed8",
+        }
+    }
+
+    pub fn bits_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Mono1 => 1,
+            PixelFormat::Gray8 | PixelFormat::Indexed8 => 8,
+            PixelFormat::Rgb565 => 16,
+            PixelFormat::Rgb888 => 24,
+            PixelFormat::Rgba8888 | PixelFormat::Bgra8888 => 32,
+        }
+    }
+
+    /// Whole pixels in `bytes` bytes.
+    pub fn pixels_in(&self, bytes: usize) -> usize {
+        bytes * 8 / self.bits_per_pixel()
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PixelFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown pixel format: {}", s))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PixelLayout {
+    pub format: PixelFormat,
+    /// Image width in pixels.
+    pub width: usize,
+    /// Bytes per row, 0 means packed rows.
+    pub stride: usize,
+    /// Offset of the first pixel relative to the start position, usually
+    /// the cursor.
+    pub offset: usize,
+    /// File offset of a 256 entry RGB888 palette for indexed formats,
+    /// a grayscale ramp is used when not set.
+    pub palette: Option<usize>,
+}
+
+impl PixelLayout {
+    pub fn row_bytes(&self) -> usize {
+        if self.stride > 0 {
+            self.stride
+        } else {
+            (self.width * self.format.bits_per_pixel()).div_ceil(8)
+        }
+    }
+
+    /// Rows are aligned so that the pixel `offset` bytes after `start`
+    /// begins one, which lets the whole document be scrolled through.
+    /// Returns the file offset of row 0 and the index of the row at
+    /// `start`, `None` if it overflows.
+    pub fn rows_at(&self, start: usize) -> Option<(usize, usize)> {
+        let first = start.checked_add(self.offset)?;
+        let row_bytes = self.row_bytes();
+        (row_bytes > 0).then(|| (first % row_bytes, first / row_bytes))
+    }
+
+    /// Rows from `origin` to the end of a document of `len` bytes, the last
+    /// one possibly partial.
+    pub fn row_count(&self, origin: usize, len: usize) -> usize {
+        match self.row_bytes() {
+            0 => 0,
+            row_bytes => len.saturating_sub(origin).div_ceil(row_bytes),
+        }
+    }
+
+    /// File offset of the pixel at `x`, `y` of the rows starting at
+    /// `origin`, `None` if it overflows.
+    pub fn byte_at(&self, origin: usize, x: usize, y: usize) -> Option<usize> {
+        origin
+            .checked_add(y.checked_mul(self.row_bytes())?)?
+            .checked_add(x.checked_mul(self.format.bits_per_pixel())? / 8)
+    }
+
+    /// Renders up to `rows` rows starting at file offset `first` as packed
+    /// RGB888 and returns the buffer with the number of rows actually
+    /// rendered. Nothing is rendered when the layout overflows.
+    pub fn render(
+        &self,
+        provider: &dyn DataProvider,
+        first: usize,
+        rows: usize,
+    ) -> (Vec<u8>, usize) {
+        let row_bytes = self.row_bytes();
+        if self.width == 0 || row_bytes == 0 {
+            return (Vec::new(), 0);
+        }
+        let size = match row_bytes.checked_mul(rows) {
+            Some(size) => size,
+            None => return (Vec::new(), 0),
+        };
+        let bytes = provider.get(first, size).unwrap_or_default();
+        let rows = std::cmp::min(rows, bytes.len().div_ceil(row_bytes));
+        let pixels = match self
+            .width
+            .checked_mul(rows)
+            .and_then(|pixels| pixels.checked_mul(3))
+        {
+            Some(pixels) => pixels,
+            None => return (Vec::new(), 0),
+        };
+        let palette = self.palette(provider);
+        let mut rgb = vec![0; pixels];
+        for y in 0..rows {
+            let row = &bytes[y * row_bytes..std::cmp::min(bytes.len(), (y + 1) * row_bytes)];
+            for x in 0..self.width {
+                if let Some(pixel) = self.pixel(row, x, &palette) {
+                    let i = (y * self.width + x) * 3;
+                    rgb[i..i + 3].copy_from_slice(&pixel);
+                }
+            }
+        }
+        (rgb, rows)
+    }
+
+    fn palette(&self, provider: &dyn DataProvider) -> Vec<[u8; 3]> {
+        let entries = self
+            .palette
+            .and_then(|offset| provider.get(offset, 256 * 3))
+            .unwrap_or_default();
+        (0..256)
+            .map(|i| match entries.get(i * 3..i * 3 + 3) {
+                Some(entry) => [entry[0], entry[1], entry[2]],
+                None => [i as u8; 3],
+            })
+            .collect()
+    }
+
+    fn pixel(&self, row: &[u8], x: usize, palette: &[[u8; 3]]) -> Option<[u8; 3]> {
+        let at = x * self.format.bits_per_pixel() / 8;
+        let bytes = row.get(at..at + self.format.bits_per_pixel().div_ceil(8))?;
+        let pixel = match self.format {
+            PixelFormat::Mono1 => {
+                let level = if bytes[0] & (0x80 >> (x % 8)) != 0 {
+                    255
+                } else {
+                    0
+                };
+                [level; 3]
+            }
+            PixelFormat::Gray8 => [bytes[0]; 3],
+            PixelFormat::Rgb565 => {
+                let value = u16::from_le_bytes([bytes[0], bytes[1]]);
+                let r = (value >> 11) & 0x1F;
+                let g = (value >> 5) & 0x3F;
+                let b = value & 0x1F;
+                [
+                    (r * 255 / 31) as u8,
+                    (g * 255 / 63) as u8,
+                    (b * 255 / 31) as u8,
+                ]
+            }
+            PixelFormat::Rgb888 | PixelFormat::Rgba8888 => [bytes[0], bytes[1], bytes[2]],
+            PixelFormat::Bgra8888 => [bytes[2], bytes[1], bytes[0]],
+            PixelFormat::Indexed8 => palette[bytes[0] as usize],
+        };
+        Some(pixel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn layout(format: PixelFormat, width: usize) -> PixelLayout {
+        PixelLayout {
+            format,
+            width,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn row_bytes() {
+        assert_eq!(layout(PixelFormat::Mono1, 10).row_bytes(), 2);
+        assert_eq!(layout(PixelFormat::Mono1, 16).row_bytes(), 2);
+        assert_eq!(layout(PixelFormat::Rgb888, 5).row_bytes(), 15);
+        let strided = PixelLayout {
+            stride: 20,
+            ..layout(PixelFormat::Rgb888, 5)
+        };
+        assert_eq!(strided.row_bytes(), 20);
+        assert_eq!(layout(PixelFormat::Gray8, 0).row_bytes(), 0);
+    }
+
+    #[test]
+    fn pixels_in() {
+        assert_eq!(PixelFormat::Mono1.pixels_in(3), 24);
+        assert_eq!(PixelFormat::Gray8.pixels_in(3), 3);
+        assert_eq!(PixelFormat::Rgb565.pixels_in(7), 3);
+        assert_eq!(PixelFormat::Rgb888.pixels_in(7), 2);
+        assert_eq!(PixelFormat::Bgra8888.pixels_in(3), 0);
+    }
+
+    #[test]
+    fn format_names() {
+        for format in PixelFormat::ALL {
+            assert_eq!(format.name().parse::<PixelFormat>().unwrap(), format);
+        }
+        assert!("rgb555".parse::<PixelFormat>().is_err());
+    }
+
+    #[test]
+    fn rows_at() {
+        let layout = layout(PixelFormat::Rgb565, 8);
+        assert_eq!(layout.rows_at(0), Some((0, 0)));
+        assert_eq!(layout.rows_at(40), Some((8, 2)));
+        let shifted = PixelLayout {
+            offset: 10,
+            ..layout
+        };
+        assert_eq!(shifted.rows_at(40), Some((2, 3)));
+        assert_eq!(shifted.rows_at(usize::MAX), None);
+        assert_eq!(PixelLayout::default().rows_at(40), None);
+    }
+
+    #[test]
+    fn row_count() {
+        let layout = layout(PixelFormat::Gray8, 16);
+        assert_eq!(layout.row_count(0, 64), 4);
+        assert_eq!(layout.row_count(0, 65), 5);
+        assert_eq!(layout.row_count(4, 64), 4);
+        assert_eq!(layout.row_count(4, 68), 4);
+        assert_eq!(layout.row_count(70, 64), 0);
+        assert_eq!(PixelLayout::default().row_count(0, 64), 0);
+    }
+
+    #[test]
+    fn byte_at() {
+        let layout = PixelLayout {
+            stride: 12,
+            ..layout(PixelFormat::Rgb565, 4)
+        };
+        assert_eq!(layout.byte_at(3, 0, 0), Some(3));
+        assert_eq!(layout.byte_at(3, 2, 1), Some(19));
+        let mono = self::layout(PixelFormat::Mono1, 20);
+        assert_eq!(mono.byte_at(0, 9, 2), Some(7));
+        assert_eq!(layout.byte_at(usize::MAX, 1, 0), None);
+        assert_eq!(layout.byte_at(0, 0, usize::MAX), None);
+    }
+
+    #[test]
+    fn render_formats() {
+        let provider = MemoryDataProvider::new(vec![0xe0, 0x07, 0x1f, 0x00]);
+        let (rgb, rows) = layout(PixelFormat::Rgb565, 2).render(&provider, 0, 4);
+        assert_eq!(rows, 1);
+        assert_eq!(rgb, [0, 255, 0, 0, 0, 255]);
+
+        let provider = MemoryDataProvider::new(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let (rgb, rows) = layout(PixelFormat::Bgra8888, 1).render(&provider, 0, 4);
+        assert_eq!(rows, 2);
+        assert_eq!(rgb, [3, 2, 1, 7, 6, 5]);
+
+        let provider = MemoryDataProvider::new(vec![0xa0]);
+        let (rgb, _) = layout(PixelFormat::Mono1, 3).render(&provider, 0, 1);
+        assert_eq!(rgb, [255, 255, 255, 0, 0, 0, 255, 255, 255]);
+    }
+
+    #[test]
+    fn render_rows() {
+        let bytes: Vec<u8> = (0..10).collect();
+        let provider = MemoryDataProvider::new(bytes);
+        let layout = PixelLayout {
+            stride: 4,
+            ..layout(PixelFormat::Gray8, 2)
+        };
+        let (rgb, rows) = layout.render(&provider, 1, 8);
+        assert_eq!(rows, 3);
+        let levels: Vec<u8> = rgb.chunks(3).map(|pixel| pixel[0]).collect();
+        // The last row is partial and its missing pixel stays black.
+        assert_eq!(levels, [1, 2, 5, 6, 9, 0]);
+    }
+
+    #[test]
+    fn render_palette() {
+        let mut bytes = vec![2, 0, 1];
+        bytes.extend((0..256 * 3).map(|i| (i % 251) as u8));
+        let provider = MemoryDataProvider::new(bytes);
+        let gray = layout(PixelFormat::Indexed8, 3);
+        assert_eq!(gray.render(&provider, 0, 1).0, [2, 2, 2, 0, 0, 0, 1, 1, 1]);
+        let indexed = PixelLayout {
+            palette: Some(3),
+            ..gray
+        };
+        assert_eq!(
+            indexed.render(&provider, 0, 1).0,
+            [6, 7, 8, 0, 1, 2, 3, 4, 5]
+        );
+    }
+}
@@ -0,0 +1,559 @@
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use regex::bytes::Regex;
+
+use crate::chunks::CHUNK_SIZE;
+use crate::{DataProvider, Error, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    pub range: Range<usize>,
+    /// What matched, e.g. the encoding of a text hit.
+    pub label: String,
+    pub kind: HitKind,
+    /// Ranges of the capture groups of a regular expression hit.
+    pub captures: Vec<Option<Range<usize>>>,
+}
+
+/// The pattern a hit came from, decides how a replacement is encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitKind {
+    Bytes,
+    Text(TextEncoding),
+    /// UTF-16 text that reads the same in either byte order.
+    Utf16,
+    Regex,
+    Guid,
+    Signature,
+}
+
+pub trait Pattern: Send + Sync {
+    /// Bytes a match may extend past the end of a chunk.
+    fn overlap(&self) -> usize;
+
+    /// Bytes before a chunk a match needs to see, e.g. for anchors.
+    fn context(&self) -> usize {
+        0
+    }
+
+    /// Appends all matches starting in the first `limit` bytes at file
+    /// offset `base` to `hits`. `bytes` begins with `min(base, context())`
+    /// bytes of context before `base`, `last` tells whether it runs to the
+    /// end of the document.
+    fn find_in(
+        &self,
+        bytes: &[u8],
+        limit: usize,
+        base: usize,
+        last: bool,
+        hits: &mut Vec<SearchHit>,
+    );
+}
+
+/// A byte sequence where `None` matches any byte, parsed from text like
+/// `4D 5A ?? ?? 50 45`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BytePattern(Vec<Option<u8>>);
+
+impl BytePattern {
+    pub fn new(bytes: Vec<Option<u8>>) -> Self {
+        Self(bytes)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn first(&self) -> Option<Option<u8>> {
+        self.0.first().copied()
+    }
+
+    /// The pattern as plain bytes, `None` if it contains wildcards.
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        self.0.iter().copied().collect()
+    }
+
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.0.len()
+            && self
+                .0
+                .iter()
+                .zip(bytes)
+                .all(|(expected, byte)| expected.is_none_or(|expected| expected == *byte))
+    }
+}
+
+impl Pattern for BytePattern {
+    fn overlap(&self) -> usize {
+        self.0.len().saturating_sub(1)
+    }
+
+    fn find_in(
+        &self,
+        bytes: &[u8],
+        limit: usize,
+        base: usize,
+        _last: bool,
+        hits: &mut Vec<SearchHit>,
+    ) {
+        if self.0.is_empty() || bytes.len() < self.0.len() {
+            return;
+        }
+        let last = std::cmp::min(limit, bytes.len() - self.0.len() + 1);
+        for start in 0..last {
+            if self.matches(&bytes[start..]) {
+                hits.push(SearchHit {
+                    range: base + start..base + start + self.0.len(),
+                    label: "hex".into(),
+                    kind: HitKind::Bytes,
+                    captures: Vec::new(),
+                });
+            }
+        }
+    }
+}
+
+impl FromStr for BytePattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut bytes = Vec::new();
+        for token in s.split_whitespace() {
+            if token == "?" || token == "??" {
+                bytes.push(None);
+                continue;
+            }
+            if token.len() % 2 != 0 {
+                anyhow::bail!("odd number of hex digits in {}", token);
+            }
+            for pair in token.as_bytes().chunks(2) {
+                let pair = std::str::from_utf8(pair)?;
+                match pair {
+                    "??" => bytes.push(None),
+                    _ => bytes.push(Some(u8::from_str_radix(pair, 16)?)),
+                }
+            }
+        }
+        if bytes.is_empty() {
+            anyhow::bail!("empty pattern");
+        }
+        Ok(Self(bytes))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextEncoding {
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl TextEncoding {
+    pub const ALL: [TextEncoding; 4] = [
+        TextEncoding::Ascii,
+        TextEncoding::Utf8,
+        TextEncoding::Utf16Le,
+        TextEncoding::Utf16Be,
+    ];
+
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            TextEncoding::Ascii | TextEncoding::Utf8 => text.as_bytes().to_vec(),
+            TextEncoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            TextEncoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextEncoding::Ascii => "ASCII",
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    fn unit_size(&self) -> usize {
+        match self {
+            TextEncoding::Ascii | TextEncoding::Utf8 => 1,
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be => 2,
+        }
+    }
+
+    fn fold(&self, unit: &[u8]) -> u16 {
+        let unit = match self {
+            TextEncoding::Ascii | TextEncoding::Utf8 => unit[0] as u16,
+            TextEncoding::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]),
+            TextEncoding::Utf16Be => u16::from_be_bytes([unit[0], unit[1]]),
+        };
+        match u8::try_from(unit) {
+            Ok(byte) => byte.to_ascii_lowercase() as u16,
+            Err(_) => unit,
+        }
+    }
+}
+
+/// Searches a string in ASCII, UTF-8 and UTF-16 at once. Pure ASCII text is
+/// reported as ASCII only since its UTF-8 encoding is identical. Case
+/// folding only applies to ASCII letters.
+pub struct TextPattern {
+    needles: Vec<(TextEncoding, Vec<u8>)>,
+    case_insensitive: bool,
+}
+
+impl TextPattern {
+    pub fn new(text: &str, case_insensitive: bool) -> Result<Self> {
+        if text.is_empty() {
+            anyhow::bail!("empty search text");
+        }
+        let first = match text.is_ascii() {
+            true => TextEncoding::Ascii,
+            false => TextEncoding::Utf8,
+        };
+        let needles = [first, TextEncoding::Utf16Le, TextEncoding::Utf16Be]
+            .into_iter()
+            .map(|encoding| (encoding, encoding.encode(text)))
+            .collect();
+        Ok(Self {
+            needles,
+            case_insensitive,
+        })
+    }
+
+    fn matches(&self, encoding: TextEncoding, needle: &[u8], bytes: &[u8]) -> bool {
+        if bytes.len() < needle.len() {
+            return false;
+        }
+        let bytes = &bytes[..needle.len()];
+        if !self.case_insensitive {
+            return needle == bytes;
+        }
+        let size = encoding.unit_size();
+        needle
+            .chunks(size)
+            .zip(bytes.chunks(size))
+            .all(|(a, b)| encoding.fold(a) == encoding.fold(b))
+    }
+
+    /// A UTF-16 string preceded or followed by a zero byte also matches in
+    /// the other byte order one byte off.
+    fn matches_shifted(&self, encoding: TextEncoding, bytes: &[u8], start: usize) -> bool {
+        let other = match encoding {
+            TextEncoding::Utf16Le => TextEncoding::Utf16Be,
+            TextEncoding::Utf16Be => TextEncoding::Utf16Le,
+            _ => return false,
+        };
+        let needle = match self.needles.iter().find(|(e, _)| *e == other) {
+            Some((_, needle)) => needle,
+            None => return false,
+        };
+        (start > 0 && self.matches(other, needle, &bytes[start - 1..]))
+            || self.matches(other, needle, &bytes[start + 1..])
+    }
+}
+
+impl Pattern for TextPattern {
+    fn overlap(&self) -> usize {
+        self.needles
+            .iter()
+            .map(|(_, needle)| needle.len().saturating_sub(1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn find_in(
+        &self,
+        bytes: &[u8],
+        limit: usize,
+        base: usize,
+        _last: bool,
+        hits: &mut Vec<SearchHit>,
+    ) {
+        for start in 0..std::cmp::min(limit, bytes.len()) {
+            for (encoding, needle) in &self.needles {
+                if !self.matches(*encoding, needle, &bytes[start..]) {
+                    continue;
+                }
+                // Both byte orders fit, keep only the even aligned hit and
+                // leave its byte order open.
+                let (label, kind) = match self.matches_shifted(*encoding, bytes, start) {
+                    true if !(base + start).is_multiple_of(2) => continue,
+                    true => ("UTF-16", HitKind::Utf16),
+                    false => (encoding.name(), HitKind::Text(*encoding)),
+                };
+                hits.push(SearchHit {
+                    range: base + start..base + start + needle.len(),
+                    label: label.into(),
+                    kind,
+                    captures: Vec::new(),
+                });
+            }
+        }
+    }
+}
+
+/// A regular expression over raw bytes with `regex::bytes` semantics.
+/// Matches longer than `overlap` bytes are split at chunk boundaries and
+/// empty matches are ignored. Anchors and word boundaries see the bytes
+/// before a chunk, matches reaching the end of a chunk that is not the end
+/// of the document are dropped as `$` and `\z` match there.
+pub struct RegexPattern {
+    regex: Regex,
+    overlap: usize,
+}
+
+impl RegexPattern {
+    pub fn new(pattern: &str, overlap: usize) -> Result<Self> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            overlap,
+        })
+    }
+}
+
+impl Pattern for RegexPattern {
+    fn overlap(&self) -> usize {
+        self.overlap
+    }
+
+    /// The widest UTF-8 character, enough to decide a word boundary.
+    fn context(&self) -> usize {
+        4
+    }
+
+    fn find_in(
+        &self,
+        bytes: &[u8],
+        limit: usize,
+        base: usize,
+        last: bool,
+        hits: &mut Vec<SearchHit>,
+    ) {
+        let context = std::cmp::min(base, self.context());
+        let offset = base - context;
+        // Continue behind a match that ran into this chunk.
+        let previous_end = hits.last().map_or(0, |hit| hit.range.end);
+        let mut start = std::cmp::min(context + previous_end.saturating_sub(base), bytes.len());
+        while let Some(captures) = self.regex.captures_at(bytes, start) {
+            let whole = captures.get(0).unwrap();
+            if whole.start() >= context + limit {
+                break;
+            }
+            if whole.end() == bytes.len() && !last {
+                break;
+            }
+            if whole.is_empty() {
+                start = whole.end() + 1;
+                if start > bytes.len() {
+                    break;
+                }
+                continue;
+            }
+            hits.push(SearchHit {
+                range: offset + whole.start()..offset + whole.end(),
+                label: "regex".into(),
+                kind: HitKind::Regex,
+                captures: captures
+                    .iter()
+                    .skip(1)
+                    .map(|group| group.map(|group| offset + group.start()..offset + group.end()))
+                    .collect(),
+            });
+            start = whole.end();
+        }
+    }
+}
+
+/// Encodes the replacement `text` for a hit: hex digits for hex hits, the
+/// encoding of the hit for text hits and UTF-8 for regular expression
+/// hits. Empty text deletes the hit.
+pub fn replacement_bytes(hit: &SearchHit, text: &str) -> Result<Vec<u8>> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    match hit.kind {
+        HitKind::Bytes => text
+            .parse::<BytePattern>()?
+            .to_bytes()
+            .ok_or_else(|| anyhow::anyhow!("wildcards cannot be written")),
+        HitKind::Text(encoding) => Ok(encoding.encode(text)),
+        HitKind::Regex => Ok(text.as_bytes().to_vec()),
+        HitKind::Utf16 => anyhow::bail!("the byte order of the hit is unknown"),
+        HitKind::Guid | HitKind::Signature => {
+            anyhow::bail!("{} hits cannot be replaced with text", hit.label)
+        }
+    }
+}
+
+/// Renders bytes for display, escaping everything but printable ASCII and
+/// truncating after `max` bytes.
+pub fn escape_bytes(bytes: &[u8], max: usize) -> String {
+    let mut text: String = bytes
+        .iter()
+        .take(max)
+        .map(|byte| match byte {
+            b' '..=b'~' => (*byte as char).to_string(),
+            _ => format!("\\x{:02X}", byte),
+        })
+        .collect();
+    if bytes.len() > max {
+        text.push('…');
+    }
+    text
+}
+
+/// Scans the whole document in overlapping chunks. `progress` is called
+/// with the number of bytes scanned after every chunk, the scan stops
+/// early when `cancel` is set.
+pub fn find_all<F>(
+    provider: &dyn DataProvider,
+    pattern: &dyn Pattern,
+    cancel: &AtomicBool,
+    mut progress: F,
+) -> Vec<SearchHit>
+where
+    F: FnMut(usize),
+{
+    let mut hits = Vec::new();
+    let overlap = pattern.overlap();
+    let mut offset = 0;
+    while offset < provider.len() && !cancel.load(Ordering::Relaxed) {
+        let context = std::cmp::min(offset, pattern.context());
+        if let Some(bytes) = provider.get(offset - context, context + CHUNK_SIZE + overlap) {
+            let last = offset - context + bytes.len() == provider.len();
+            pattern.find_in(&bytes, CHUNK_SIZE, offset, last, &mut hits);
+        }
+        offset += CHUNK_SIZE;
+        progress(std::cmp::min(offset, provider.len()));
+    }
+    hits
+}
+
+/// Running maximum of the ends of `hits`, which are sorted by start, for
+/// `overlapping`.
+pub(crate) fn max_ends(hits: &[SearchHit]) -> Vec<usize> {
+    hits.iter()
+        .scan(0, |max_end, hit| {
+            *max_end = std::cmp::max(*max_end, hit.range.end);
+            Some(*max_end)
+        })
+        .collect()
+}
+
+/// The hits overlapping `range`, including long hits that start far
+/// before it.
+pub(crate) fn overlapping<'a>(
+    hits: &'a [SearchHit],
+    max_ends: &[usize],
+    range: Range<usize>,
+) -> impl Iterator<Item = &'a SearchHit> {
+    let first = max_ends.partition_point(|end| *end <= range.start);
+    hits[first..]
+        .iter()
+        .take_while(move |hit| hit.range.start < range.end)
+        .filter(move |hit| hit.range.end > range.start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn text_hits(text: &str, bytes: &[u8]) -> Vec<(Range<usize>, String)> {
+        let mut hits = Vec::new();
+        TextPattern::new(text, false)
+            .unwrap()
+            .find_in(bytes, bytes.len(), 0, true, &mut hits);
+        hits.into_iter().map(|hit| (hit.range, hit.label)).collect()
+    }
+
+    fn hit(range: Range<usize>) -> SearchHit {
+        SearchHit {
+            range,
+            label: "hex".into(),
+            kind: HitKind::Bytes,
+            captures: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn utf16_byte_order() {
+        assert_eq!(
+            text_hits("AB", b"\xFF\xFFA\0B\0\xFF"),
+            [(2..6, "UTF-16LE".into())]
+        );
+        assert_eq!(
+            text_hits("AB", b"\xFF\0A\0B\xFF"),
+            [(1..5, "UTF-16BE".into())]
+        );
+        // Odd aligned without a zero byte on the other side.
+        assert_eq!(
+            text_hits("AB", b"\xFFA\0B\0\xFF"),
+            [(1..5, "UTF-16LE".into())]
+        );
+        // Either byte order fits, the hit is not labelled with the wrong one.
+        assert_eq!(text_hits("AB", b"\xFF\0A\0B\0"), [(2..6, "UTF-16".into())]);
+        assert_eq!(text_hits("AB", b"\0A\0B\0\xFF"), [(0..4, "UTF-16".into())]);
+    }
+
+    #[test]
+    fn regex_anchors_at_chunk_boundaries() {
+        let mut bytes = vec![b'a'; CHUNK_SIZE + 8];
+        bytes[CHUNK_SIZE - 1] = b'\n';
+        let provider = MemoryDataProvider::new(bytes);
+        let starts = |pattern: &str| {
+            let pattern = RegexPattern::new(pattern, 16).unwrap();
+            find_all(&provider, &pattern, &AtomicBool::new(false), |_| {})
+                .into_iter()
+                .map(|hit| hit.range.start)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(starts(r"\Aa"), [0]);
+        assert_eq!(starts(r"^a"), [0]);
+        assert_eq!(starts(r"(?m)^a"), [0, CHUNK_SIZE]);
+        assert_eq!(starts(r"\ba{4}"), [0, CHUNK_SIZE]);
+        let pattern = RegexPattern::new(r"\n(a)", 16).unwrap();
+        let hits = find_all(&provider, &pattern, &AtomicBool::new(false), |_| {});
+        assert_eq!(hits[0].captures, [Some(CHUNK_SIZE..CHUNK_SIZE + 1)]);
+    }
+
+    #[test]
+    fn regex_end_anchors_at_chunk_boundaries() {
+        let len = 2 * CHUNK_SIZE + 8;
+        let provider = MemoryDataProvider::new(vec![b'a'; len]);
+        let ranges = |pattern: &str| {
+            let pattern = RegexPattern::new(pattern, 16).unwrap();
+            find_all(&provider, &pattern, &AtomicBool::new(false), |_| {})
+                .into_iter()
+                .map(|hit| (hit.range.start, hit.range.end))
+                .collect::<Vec<_>>()
+        };
+        // The second chunk reads to the end of the document, the first one
+        // ends in the middle of the run.
+        assert_eq!(ranges(r"[a-z]+$"), [(CHUNK_SIZE, len)]);
+        assert_eq!(ranges(r"a\z"), [(len - 1, len)]);
+        assert_eq!(ranges(r"(?m)a{4}$"), [(len - 4, len)]);
+    }
+
+    #[test]
+    fn overlapping_hits() {
+        let hits = [hit(0..1000), hit(10..12), hit(20..22), hit(500..502)];
+        let ends = max_ends(&hits);
+        let starts = |range| {
+            overlapping(&hits, &ends, range)
+                .map(|hit| hit.range.start)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(starts(400..600), [0, 500]);
+        assert_eq!(starts(11..21), [0, 10, 20]);
+        assert!(starts(1000..2000).is_empty());
+        assert_eq!(starts(999..1000), [0]);
+    }
+}
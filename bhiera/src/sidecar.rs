@@ -0,0 +1,231 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::chunks::for_each_chunk;
+use crate::{DataProvider, Result};
+
+const SIDECAR_NAME: &str = ".gbhiera.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub offset: usize,
+    pub color: (u8, u8, u8),
+}
+
+/// A label and free-text comment attached to a byte range.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub label: String,
+    #[serde(default)]
+    pub comment: String,
+    pub range: Range<usize>,
+    pub color: (u8, u8, u8),
+}
+
+/// Everything we remember about one document.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notes {
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    name: String,
+    sha256: String,
+    #[serde(flatten)]
+    notes: Notes,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Entries {
+    files: Vec<Entry>,
+}
+
+/// Notes of the documents in one directory, stored in `.gbhiera.json`
+/// next to them. Entries are found by file name, or by content hash if
+/// the document was renamed.
+#[derive(Clone, Debug)]
+pub struct Sidecar {
+    path: PathBuf,
+    name: String,
+    /// Content hash of the document, unknown until it was computed.
+    sha256: Option<String>,
+}
+
+impl Sidecar {
+    pub fn new(document: &Path) -> Self {
+        Self {
+            path: document.with_file_name(SIDECAR_NAME),
+            name: document
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            sha256: None,
+        }
+    }
+
+    pub fn set_sha256(&mut self, sha256: String) {
+        self.sha256 = Some(sha256);
+    }
+
+    pub fn to_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Notes> {
+        let entries = self.entries()?;
+        let entry = self.find(&entries).map(|index| &entries.files[index]);
+        Ok(entry.map(|entry| entry.notes.clone()).unwrap_or_default())
+    }
+
+    /// Stores `notes` for the document, renamed documents take over the
+    /// entry with the same content hash.
+    pub fn save(&self, notes: &Notes) -> Result<()> {
+        let mut entries = self.entries()?;
+        let index = self.find(&entries);
+        // Keep the stored hash while the current one is being computed.
+        let sha256 = match (&self.sha256, index) {
+            (Some(sha256), _) => sha256.clone(),
+            (None, Some(index)) => entries.files[index].sha256.clone(),
+            (None, None) => String::new(),
+        };
+        let entry = Entry {
+            name: self.name.clone(),
+            sha256,
+            notes: notes.clone(),
+        };
+        match index {
+            Some(index) => entries.files[index] = entry,
+            None => entries.files.push(entry),
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(())
+    }
+
+    /// The entry of the document by name, or the entry of a file with the
+    /// same content that no longer exists, i.e. was renamed. Copies keep
+    /// their own entries.
+    fn find(&self, entries: &Entries) -> Option<usize> {
+        entries
+            .files
+            .iter()
+            .position(|entry| entry.name == self.name)
+            .or_else(|| {
+                entries.files.iter().position(|entry| {
+                    Some(&entry.sha256) == self.sha256.as_ref()
+                        && !self.path.with_file_name(&entry.name).exists()
+                })
+            })
+    }
+
+    fn entries(&self) -> Result<Entries> {
+        if !self.path.exists() {
+            return Ok(Entries::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(&self.path)?)?)
+    }
+}
+
+/// SHA-256 of the document as hex digits, which identifies it in the
+/// sidecar. Stops early when `cancel` is set.
+pub fn content_hash(provider: &dyn DataProvider, cancel: &AtomicBool) -> String {
+    let mut hasher = Sha256::new();
+    for_each_chunk(
+        provider,
+        0..provider.len(),
+        cancel,
+        |_| {},
+        |_, bytes| hasher.update(bytes),
+    );
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Writes annotations as a JSON array to share them outside the sidecar.
+pub fn export_annotations(path: &Path, annotations: &[Annotation]) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(annotations)?)?;
+    Ok(())
+}
+
+pub fn import_annotations(path: &Path) -> Result<Vec<Annotation>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(name: &str) -> Notes {
+        Notes {
+            bookmarks: vec![Bookmark {
+                name: name.into(),
+                offset: 16,
+                color: (255, 0, 0),
+            }],
+            annotations: Vec::new(),
+        }
+    }
+
+    /// An empty directory for the test named `name`.
+    fn directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("bhiera-sidecar-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn sidecar(document: &Path, sha256: &str) -> Sidecar {
+        let mut sidecar = Sidecar::new(document);
+        sidecar.set_sha256(sha256.into());
+        sidecar
+    }
+
+    #[test]
+    fn renamed_document() {
+        let directory = directory("renamed");
+        let original = directory.join("original.bin");
+        fs::write(&original, b"data").unwrap();
+        sidecar(&original, "hash").save(&notes("entry")).unwrap();
+        let renamed = directory.join("renamed.bin");
+        fs::rename(&original, &renamed).unwrap();
+        let sidecar = sidecar(&renamed, "hash");
+        assert_eq!(sidecar.load().unwrap(), notes("entry"));
+        sidecar.save(&notes("saved")).unwrap();
+        assert_eq!(sidecar.entries().unwrap().files.len(), 1);
+        // Notes are found by name before the hash is known.
+        assert_eq!(Sidecar::new(&renamed).load().unwrap(), notes("saved"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn identical_copies() {
+        let directory = directory("copies");
+        let original = directory.join("original.bin");
+        let copy = directory.join("copy.bin");
+        fs::write(&original, b"data").unwrap();
+        fs::write(&copy, b"data").unwrap();
+        sidecar(&original, "hash").save(&notes("original")).unwrap();
+        let copy_sidecar = sidecar(&copy, "hash");
+        assert_eq!(copy_sidecar.load().unwrap(), Notes::default());
+        copy_sidecar.save(&notes("copy")).unwrap();
+        assert_eq!(
+            sidecar(&original, "hash").load().unwrap(),
+            notes("original")
+        );
+        assert_eq!(copy_sidecar.load().unwrap(), notes("copy"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,537 @@
+use std::fs;
+use std::path::Path;
+
+use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+use serde::Deserialize;
+
+use crate::checksum::crc32;
+use crate::{BytePattern, HitKind, Pattern, Result, SearchHit};
+
+/// Bytes after the start of a signature that validity checks may read.
+const HEADER_SIZE: usize = 4096;
+/// Output buffer for trial decompression of deflate streams.
+const INFLATE_SIZE: usize = 1 << 16;
+/// Bytes a deflate stream must decompress to.
+const MIN_INFLATED: usize = 16;
+
+/// Validity check of a built-in signature, returning a description of the
+/// match or `None` for a false positive.
+type Check = fn(&[u8]) -> Option<String>;
+
+/// Magic bytes of an embedded file format, like `binwalk` scans for.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    pub name: String,
+    pub magic: BytePattern,
+    /// Offset of the magic from the start of the file format.
+    pub offset: usize,
+    check: Option<Check>,
+}
+
+impl Signature {
+    pub fn new(name: &str, magic: BytePattern, offset: usize) -> Self {
+        Self {
+            name: name.into(),
+            magic,
+            offset,
+            check: None,
+        }
+    }
+
+    fn builtin(name: &str, magic: &str, offset: usize, check: Check) -> Self {
+        Self {
+            name: name.into(),
+            magic: magic.parse().expect("valid built-in magic"),
+            offset,
+            check: Some(check),
+        }
+    }
+
+    /// The description of a match at the start of `bytes`.
+    fn describe(&self, bytes: &[u8]) -> Option<String> {
+        if !self.magic.matches(bytes.get(self.offset..)?) {
+            return None;
+        }
+        match self.check {
+            Some(check) => check(bytes).map(|details| match details.is_empty() {
+                true => self.name.clone(),
+                false => format!("{}, {}", self.name, details),
+            }),
+            None => Some(self.name.clone()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SignatureFile {
+    #[serde(default)]
+    signature: Vec<SignatureEntry>,
+}
+
+#[derive(Deserialize)]
+struct SignatureEntry {
+    name: String,
+    magic: String,
+    #[serde(default)]
+    offset: usize,
+}
+
+/// Signatures matched in a single pass, usable as a search pattern.
+#[derive(Clone, Debug)]
+pub struct SignatureSet {
+    signatures: Vec<Signature>,
+    /// Signatures by the first byte of their magic.
+    by_first_byte: Vec<Vec<usize>>,
+    /// Signatures whose magic starts with a wildcard.
+    wildcards: Vec<usize>,
+}
+
+impl SignatureSet {
+    pub fn new(signatures: Vec<Signature>) -> Self {
+        let mut by_first_byte = vec![Vec::new(); 256];
+        let mut wildcards = Vec::new();
+        for (index, signature) in signatures.iter().enumerate() {
+            match signature.magic.first() {
+                Some(Some(byte)) => by_first_byte[byte as usize].push(index),
+                Some(None) => wildcards.push(index),
+                None => {}
+            }
+        }
+        Self {
+            signatures,
+            by_first_byte,
+            wildcards,
+        }
+    }
+
+    pub fn builtin() -> Vec<Signature> {
+        vec![
+            Signature::builtin("ELF", "7f 45 4c 46", 0, check_elf),
+            Signature::builtin("PE", "4d 5a", 0, check_pe),
+            Signature::builtin("gzip", "1f 8b 08", 0, check_gzip),
+            Signature::builtin("xz", "fd 37 7a 58 5a 00", 0, check_xz),
+            Signature::builtin("LZMA", "5d 00 00", 0, check_lzma),
+            Signature::builtin("zlib", "78 01", 0, check_zlib),
+            Signature::builtin("zlib", "78 5e", 0, check_zlib),
+            Signature::builtin("zlib", "78 9c", 0, check_zlib),
+            Signature::builtin("zlib", "78 da", 0, check_zlib),
+            Signature::builtin("squashfs", "68 73 71 73", 0, check_squashfs_le),
+            Signature::builtin("squashfs", "73 71 73 68", 0, check_squashfs_be),
+            Signature::builtin("JPEG", "ff d8 ff", 0, check_jpeg),
+            Signature::builtin("PNG", "89 50 4e 47 0d 0a 1a 0a", 0, check_png),
+            Signature::builtin("BMP", "42 4d", 0, check_bmp),
+            Signature::builtin("UEFI firmware volume", "5f 46 56 48", 40, check_fv),
+            Signature::builtin("DER certificate", "30 82 ?? ?? 30 82", 0, check_der),
+            Signature::builtin(
+                "PEM certificate",
+                "2d 2d 2d 2d 2d 42 45 47 49 4e 20 43 45 52 54 49 46 49 43 41 54 45 2d",
+                0,
+                |_| Some(String::new()),
+            ),
+            Signature::builtin("cpio", "30 37 30 37 30", 0, check_cpio),
+            Signature::builtin("U-Boot image", "27 05 19 56", 0, check_uimage),
+        ]
+    }
+
+    /// Loads user signatures from a TOML file:
+    ///
+    /// ```toml
+    /// [[signature]]
+    /// name = "Intel microcode"
+    /// magic = "01 00 00 00 ?? ?? ?? ?? ?? ?? ?? ?? 01 00 00 00"
+    /// offset = 0
+    /// ```
+    ///
+    /// `magic` uses the syntax of hex searches, `offset` is the position of
+    /// the magic from the start of the format and defaults to 0.
+    pub fn load(path: &Path) -> Result<Vec<Signature>> {
+        let file: SignatureFile = toml::from_str(&fs::read_to_string(path)?)?;
+        file.signature
+            .into_iter()
+            .map(|entry| {
+                let magic = entry
+                    .magic
+                    .parse()
+                    .map_err(|err| anyhow::anyhow!("signature {}: {}", entry.name, err))?;
+                Ok(Signature::new(&entry.name, magic, entry.offset))
+            })
+            .collect()
+    }
+
+    fn max_offset(&self) -> usize {
+        self.signatures
+            .iter()
+            .map(|signature| signature.offset)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Pattern for SignatureSet {
+    fn overlap(&self) -> usize {
+        let magic = self
+            .signatures
+            .iter()
+            .map(|signature| signature.offset + signature.magic.len())
+            .max()
+            .unwrap_or(0);
+        std::cmp::max(magic, HEADER_SIZE)
+    }
+
+    fn find_in(
+        &self,
+        bytes: &[u8],
+        limit: usize,
+        base: usize,
+        _last: bool,
+        hits: &mut Vec<SearchHit>,
+    ) {
+        let end = std::cmp::min(bytes.len(), limit + self.max_offset());
+        let mut found = Vec::new();
+        for position in 0..end {
+            let candidates = self.by_first_byte[bytes[position] as usize]
+                .iter()
+                .chain(&self.wildcards);
+            for index in candidates {
+                let signature = &self.signatures[*index];
+                let start = match position.checked_sub(signature.offset) {
+                    Some(start) if start < limit => start,
+                    _ => continue,
+                };
+                if let Some(label) = signature.describe(&bytes[start..]) {
+                    found.push(SearchHit {
+                        range: base + start..base + position + signature.magic.len(),
+                        label,
+                        kind: HitKind::Signature,
+                        captures: Vec::new(),
+                    });
+                }
+            }
+        }
+        // Signatures with a magic offset are found after later ones.
+        found.sort_by_key(|hit| hit.range.start);
+        hits.append(&mut found);
+    }
+}
+
+fn u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u64_le(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+fn u16_be(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u64_be(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+fn check_elf(bytes: &[u8]) -> Option<String> {
+    let class = match bytes.get(4)? {
+        1 => "32-bit",
+        2 => "64-bit",
+        _ => return None,
+    };
+    let (order, kind) = match bytes.get(5)? {
+        1 => ("LSB", u16_le(bytes, 16)?),
+        2 => ("MSB", u16_be(bytes, 16)?),
+        _ => return None,
+    };
+    let kind = match kind {
+        1 => "relocatable",
+        2 => "executable",
+        3 => "shared object",
+        4 => "core",
+        _ => return None,
+    };
+    (*bytes.get(6)? == 1).then(|| format!("{} {} {}", class, order, kind))
+}
+
+fn check_pe(bytes: &[u8]) -> Option<String> {
+    let header = u32_le(bytes, 0x3c)? as usize;
+    if !(0x40..HEADER_SIZE - 24).contains(&header) || bytes.get(header..header + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let machine = match u16_le(bytes, header + 4)? {
+        0x014c => "i386".into(),
+        0x8664 => "x86-64".into(),
+        0x01c0 | 0x01c2 | 0x01c4 => "ARM".into(),
+        0xaa64 => "ARM64".into(),
+        0x0ebc => "EFI byte code".into(),
+        0x5064 => "RISC-V 64".into(),
+        machine => format!("machine {:#06x}", machine),
+    };
+    Some(machine)
+}
+
+/// Whether the start of `bytes` decompresses without error to a few bytes,
+/// random data rarely does.
+fn inflates(bytes: &[u8], flags: u32) -> bool {
+    let mut decompressor = Box::new(DecompressorOxide::new());
+    let mut output = vec![0; INFLATE_SIZE];
+    let flags = flags
+        | inflate_flags::TINFL_FLAG_HAS_MORE_INPUT
+        | inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF
+        | inflate_flags::TINFL_FLAG_IGNORE_ADLER32;
+    let (status, _, written) = decompress(&mut decompressor, bytes, &mut output, 0, flags);
+    let valid = matches!(
+        status,
+        TINFLStatus::Done | TINFLStatus::NeedsMoreInput | TINFLStatus::HasMoreOutput
+    );
+    valid && written >= MIN_INFLATED
+}
+
+fn check_gzip(bytes: &[u8]) -> Option<String> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+    let flags = *bytes.get(3)?;
+    if flags & 0xe0 != 0 {
+        return None;
+    }
+    // Optional fields sit between the header and the deflate stream.
+    let mut offset = 10;
+    if flags & FEXTRA != 0 {
+        offset += 2 + usize::from(u16_le(bytes, offset)?);
+    }
+    let mut details = String::new();
+    if flags & FNAME != 0 {
+        let end = offset + bytes.get(offset..)?.iter().position(|byte| *byte == 0)?;
+        details = format!("name {}", String::from_utf8_lossy(&bytes[offset..end]));
+        offset = end + 1;
+    }
+    if flags & FCOMMENT != 0 {
+        offset += bytes.get(offset..)?.iter().position(|byte| *byte == 0)? + 1;
+    }
+    if flags & FHCRC != 0 {
+        offset += 2;
+    }
+    inflates(bytes.get(offset..)?, 0).then_some(details)
+}
+
+fn check_xz(bytes: &[u8]) -> Option<String> {
+    let flags = u16_be(bytes, 6)?;
+    let stream_crc = u32_le(bytes, 8)?;
+    (flags & 0xfff0 == 0 && crc32(bytes.get(6..8)?) == stream_crc).then(String::new)
+}
+
+fn check_lzma(bytes: &[u8]) -> Option<String> {
+    let dictionary = u32_le(bytes, 1)?;
+    let size = u64_le(bytes, 5)?;
+    // Dictionaries are 2^n or 2^n + 2^(n-1) bytes.
+    let valid = dictionary >= 4096
+        && (dictionary.is_power_of_two()
+            || (dictionary.is_multiple_of(3) && (dictionary / 3).is_power_of_two()));
+    if !valid || (size != u64::MAX && size >= 1 << 40) {
+        return None;
+    }
+    match size {
+        u64::MAX => Some(format!("dictionary {} KiB", dictionary / 1024)),
+        size => Some(format!(
+            "dictionary {} KiB, {} bytes uncompressed",
+            dictionary / 1024,
+            size
+        )),
+    }
+}
+
+fn check_zlib(bytes: &[u8]) -> Option<String> {
+    let header = u16_be(bytes, 0)?;
+    let preset_dictionary = header & 0x20 != 0;
+    let valid = header.is_multiple_of(31)
+        && !preset_dictionary
+        && inflates(bytes, inflate_flags::TINFL_FLAG_PARSE_ZLIB_HEADER);
+    valid.then(String::new)
+}
+
+fn check_squashfs(major: u16, block_size: u32, block_log: u16, used: u64) -> Option<String> {
+    let valid = major == 4
+        && block_size.is_power_of_two()
+        && (4096..=1 << 20).contains(&block_size)
+        && block_size.trailing_zeros() == block_log as u32;
+    valid.then(|| format!("version 4, {} bytes", used))
+}
+
+fn check_squashfs_le(bytes: &[u8]) -> Option<String> {
+    check_squashfs(
+        u16_le(bytes, 28)?,
+        u32_le(bytes, 12)?,
+        u16_le(bytes, 22)?,
+        u64_le(bytes, 40)?,
+    )
+}
+
+fn check_squashfs_be(bytes: &[u8]) -> Option<String> {
+    check_squashfs(
+        u16_be(bytes, 28)?,
+        u32_be(bytes, 12)?,
+        u16_be(bytes, 22)?,
+        u64_be(bytes, 40)?,
+    )
+}
+
+fn check_jpeg(bytes: &[u8]) -> Option<String> {
+    match bytes.get(3)? {
+        0xe0 if bytes.get(6..11)? == b"JFIF\0" => Some("JFIF".into()),
+        0xe1 if bytes.get(6..11)? == b"Exif\0" => Some("Exif".into()),
+        0xdb | 0xee | 0xe0..=0xef => Some(String::new()),
+        _ => None,
+    }
+}
+
+fn check_png(bytes: &[u8]) -> Option<String> {
+    if u32_be(bytes, 8)? != 13 || bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some(format!("{}x{}", u32_be(bytes, 16)?, u32_be(bytes, 20)?))
+}
+
+fn check_bmp(bytes: &[u8]) -> Option<String> {
+    let size = u32_le(bytes, 2)?;
+    let data = u32_le(bytes, 10)?;
+    let header = u32_le(bytes, 14)?;
+    let valid = u32_le(bytes, 6)? == 0
+        && data < size
+        && data >= 14 + header
+        && [12, 40, 52, 56, 108, 124].contains(&header);
+    if !valid {
+        return None;
+    }
+    match header {
+        12 => Some(format!("{}x{}", u16_le(bytes, 18)?, u16_le(bytes, 20)?)),
+        _ => Some(format!(
+            "{}x{}",
+            u32_le(bytes, 18)? as i32,
+            (u32_le(bytes, 22)? as i32).abs()
+        )),
+    }
+}
+
+fn check_fv(bytes: &[u8]) -> Option<String> {
+    let zero_vector = bytes.get(..16)?.iter().all(|byte| *byte == 0);
+    let length = u64_le(bytes, 32)?;
+    let header = u16_le(bytes, 48)? as u64;
+    let revision = *bytes.get(55)?;
+    let valid = zero_vector && header >= 56 && header.is_multiple_of(8) && length >= header;
+    (valid && (1..=2).contains(&revision)).then(|| format!("{} bytes", length))
+}
+
+fn check_der(bytes: &[u8]) -> Option<String> {
+    let outer = u16_be(bytes, 2)? as usize;
+    let inner = u16_be(bytes, 6)? as usize;
+    (inner + 4 <= outer).then(|| format!("{} bytes", outer + 4))
+}
+
+fn check_cpio(bytes: &[u8]) -> Option<String> {
+    let header = match bytes.get(5)? {
+        b'1' | b'2' => 110,
+        b'7' => 76,
+        _ => return None,
+    };
+    let fields = bytes.get(6..header)?;
+    let digits = match bytes.get(5)? {
+        b'7' => fields.iter().all(|byte| (b'0'..=b'7').contains(byte)),
+        _ => fields.iter().all(u8::is_ascii_hexdigit),
+    };
+    if !digits {
+        return None;
+    }
+    let name_size = match bytes.get(5)? {
+        b'7' => usize::from_str_radix(std::str::from_utf8(bytes.get(59..65)?).ok()?, 8).ok()?,
+        _ => usize::from_str_radix(std::str::from_utf8(bytes.get(94..102)?).ok()?, 16).ok()?,
+    };
+    let name = bytes.get(header..header + name_size.saturating_sub(1))?;
+    Some(format!("\"{}\"", String::from_utf8_lossy(name)))
+}
+
+fn check_uimage(bytes: &[u8]) -> Option<String> {
+    let mut header = bytes.get(..64)?.to_vec();
+    let header_crc = u32_be(&header, 4)?;
+    header[4..8].fill(0);
+    if crc32(&header) != header_crc {
+        return None;
+    }
+    let name = &header[32..64];
+    let name = &name[..name
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(name.len())];
+    Some(format!(
+        "\"{}\", {} bytes",
+        String::from_utf8_lossy(name),
+        u32_be(&header, 12)?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniz_oxide::deflate::compress_to_vec;
+
+    fn gzip(flags: u8, fields: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x1f, 0x8b, 8, flags, 0, 0, 0, 0, 0, 3];
+        bytes.extend_from_slice(fields);
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn gzip_optional_fields() {
+        let deflated = compress_to_vec(&[b'a'; 256], 6);
+        assert_eq!(check_gzip(&gzip(0, b"", &deflated)), Some(String::new()));
+        let fields = b"\x02\x00xyname.txt\0comment\0\xab\xcd";
+        assert_eq!(
+            check_gzip(&gzip(0x1e, fields, &deflated)),
+            Some("name name.txt".into())
+        );
+        // Without a valid deflate stream after the fields.
+        assert_eq!(check_gzip(&gzip(0x1e, fields, &[0xff; 64])), None);
+        assert_eq!(check_gzip(&gzip(0x08, b"unterminated", &[])), None);
+        assert_eq!(check_gzip(&gzip(0x20, b"", &deflated)), None);
+    }
+
+    fn lzma(dictionary: u32) -> Vec<u8> {
+        let mut bytes = vec![0x5d];
+        bytes.extend_from_slice(&dictionary.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn lzma_dictionary_sizes() {
+        assert_eq!(
+            check_lzma(&lzma(1 << 23)),
+            Some("dictionary 8192 KiB".into())
+        );
+        assert!(check_lzma(&lzma(3 << 22)).is_some());
+        assert_eq!(check_lzma(&lzma(0x3000001)), None);
+        assert_eq!(check_lzma(&lzma(5 << 20)), None);
+        assert_eq!(check_lzma(&lzma(2048)), None);
+    }
+}
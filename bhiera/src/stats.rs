@@ -0,0 +1,353 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::chunks::{for_each_chunk, CHUNK_SIZE};
+use crate::{ByteClass, DataProvider};
+
+pub struct Histogram {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Histogram {
+    pub fn new(bytes: &[u8]) -> Self {
+        let mut histogram = Self {
+            counts: [0; 256],
+            total: 0,
+        };
+        histogram.add(bytes);
+        histogram
+    }
+
+    pub fn add(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.counts[*byte as usize] += 1;
+        }
+        self.total += bytes.len() as u64;
+    }
+
+    pub fn counts(&self) -> &[u64; 256] {
+        &self.counts
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Shannon entropy in bits per byte, from 0.0 to 8.0.
+    pub fn entropy(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let total = self.total as f64;
+        let entropy: f64 = self
+            .counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f64 / total;
+                -p * p.log2()
+            })
+            .sum();
+        // A single byte value sums up to -0.0, which `max` may keep.
+        entropy.abs()
+    }
+
+    /// Arithmetic mean of the byte values, 127.5 for random data.
+    pub fn mean(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(byte, count)| byte as f64 * *count as f64)
+            .sum();
+        sum / self.total as f64
+    }
+
+    /// Chi-square against a uniform distribution, about 255 for random
+    /// data.
+    pub fn chi_square(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let expected = self.total as f64 / 256.0;
+        self.counts
+            .iter()
+            .map(|count| {
+                let difference = *count as f64 - expected;
+                difference * difference / expected
+            })
+            .sum()
+    }
+
+    /// Proportion of printable ASCII and whitespace, from 0.0 to 1.0.
+    pub fn printable_ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let printable: u64 = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(byte, _)| {
+                matches!(
+                    ByteClass::of(*byte as u8),
+                    ByteClass::Printable | ByteClass::Whitespace
+                )
+            })
+            .map(|(_, count)| *count)
+            .sum();
+        printable as f64 / self.total as f64
+    }
+
+    /// The `count` most common bytes, most common first.
+    pub fn most_common(&self, count: usize) -> Vec<(u8, u64)> {
+        let mut bytes: Vec<(u8, u64)> = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(byte, count)| (byte as u8, *count))
+            .collect();
+        bytes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        bytes.truncate(count);
+        bytes
+    }
+}
+
+/// Counts the bytes of `range`, or of the part before `cancel` was set.
+pub fn histogram<F>(
+    provider: &dyn DataProvider,
+    range: Range<usize>,
+    cancel: &AtomicBool,
+    progress: F,
+) -> Histogram
+where
+    F: FnMut(usize),
+{
+    let mut histogram = Histogram::new(&[]);
+    for_each_chunk(provider, range, cancel, progress, |_, bytes| {
+        histogram.add(bytes)
+    });
+    histogram
+}
+
+/// Entropy above this fraction of 8 bits starts a high entropy region.
+const RISING_EDGE: f64 = 0.95 * 8.0;
+/// Entropy below this fraction of 8 bits ends a high entropy region.
+const FALLING_EDGE: f64 = 0.85 * 8.0;
+
+/// Shannon entropy of consecutive windows over a whole document.
+pub struct EntropyProfile {
+    pub window: usize,
+    pub len: usize,
+    pub entropies: Vec<f64>,
+}
+
+/// Offset where the entropy changes sharply, like between code and
+/// compressed data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntropyEdge {
+    pub offset: usize,
+    pub rising: bool,
+}
+
+impl EntropyProfile {
+    /// `progress` is called with the number of bytes done, the profile is
+    /// incomplete when `cancel` is set.
+    pub fn new<F>(
+        provider: &dyn DataProvider,
+        window: usize,
+        cancel: &AtomicBool,
+        mut progress: F,
+    ) -> Self
+    where
+        F: FnMut(usize),
+    {
+        let window = window.max(1);
+        let len = provider.len();
+        let mut entropies = Vec::with_capacity(len.div_ceil(window));
+        let mut offset = 0;
+        while offset < len && !cancel.load(Ordering::Relaxed) {
+            let size = std::cmp::min(window, len - offset);
+            let entropy = provider
+                .get(offset, size)
+                .map_or(0.0, |bytes| Histogram::new(&bytes).entropy());
+            entropies.push(entropy);
+            offset += size;
+            if offset % CHUNK_SIZE < window {
+                progress(offset);
+            }
+        }
+        Self {
+            window,
+            len,
+            entropies,
+        }
+    }
+
+    pub fn entropy_at(&self, offset: usize) -> Option<f64> {
+        self.entropies.get(offset / self.window).copied()
+    }
+
+    /// Region boundaries, using the thresholds of `binwalk -E`.
+    pub fn edges(&self) -> Vec<EntropyEdge> {
+        let mut edges = Vec::new();
+        let mut high = self.entropies.first().is_some_and(|e| *e >= RISING_EDGE);
+        for (index, entropy) in self.entropies.iter().enumerate().skip(1) {
+            let rising = !high && *entropy >= RISING_EDGE;
+            let falling = high && *entropy <= FALLING_EDGE;
+            if rising || falling {
+                high = rising;
+                edges.push(EntropyEdge {
+                    offset: index * self.window,
+                    rising,
+                });
+            }
+        }
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn empty_histogram() {
+        let histogram = Histogram::new(&[]);
+        assert_eq!(histogram.total(), 0);
+        assert_eq!(histogram.entropy(), 0.0);
+        assert_eq!(histogram.chi_square(), 0.0);
+        assert_eq!(histogram.mean(), 0.0);
+        assert_eq!(histogram.printable_ratio(), 0.0);
+        assert!(histogram.most_common(8).is_empty());
+    }
+
+    #[test]
+    fn single_byte_value() {
+        let histogram = Histogram::new(&[b'A'; 100]);
+        assert_eq!(histogram.entropy(), 0.0);
+        assert!(histogram.entropy().is_sign_positive());
+        // All bytes in one of 256 bins.
+        assert!(close(histogram.chi_square(), 100.0 * 255.0));
+        assert!(close(histogram.mean(), 65.0));
+        assert!(close(histogram.printable_ratio(), 1.0));
+        assert_eq!(histogram.most_common(8), [(b'A', 100)]);
+    }
+
+    #[test]
+    fn uniform_bytes() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let histogram = Histogram::new(&bytes);
+        assert!(close(histogram.entropy(), 8.0));
+        assert!(close(histogram.chi_square(), 0.0));
+        assert!(close(histogram.mean(), 127.5));
+        // 94 graphic characters and 6 whitespace bytes.
+        assert!(close(histogram.printable_ratio(), 100.0 / 256.0));
+    }
+
+    #[test]
+    fn most_common_ties() {
+        let histogram = Histogram::new(b"bbaadcd");
+        assert_eq!(histogram.most_common(3), [(b'a', 2), (b'b', 2), (b'd', 2)]);
+        assert_eq!(histogram.most_common(8).last(), Some(&(b'c', 1)));
+    }
+
+    #[test]
+    fn histogram_of_range() {
+        let provider = MemoryDataProvider::new(b"aaabbb".to_vec());
+        let counted = histogram(&provider, 2..4, &AtomicBool::new(false), |_| {});
+        assert_eq!(counted.most_common(8), [(b'a', 1), (b'b', 1)]);
+        let cancelled = histogram(&provider, 0..6, &AtomicBool::new(true), |_| {});
+        assert_eq!(cancelled.total(), 0);
+    }
+
+    /// Bytes of an xorshift generator, high entropy in large windows.
+    fn random_bytes(count: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        (0..count)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    fn profile(bytes: Vec<u8>, window: usize) -> EntropyProfile {
+        let provider = MemoryDataProvider::new(bytes);
+        EntropyProfile::new(&provider, window, &AtomicBool::new(false), |_| {})
+    }
+
+    #[test]
+    fn rising_edge_at_window_boundary() {
+        let mut bytes = vec![0; 2 * 4096];
+        bytes.extend(random_bytes(2 * 4096));
+        let profile = profile(bytes, 4096);
+        assert_eq!(profile.entropies.len(), 4);
+        assert!(profile.entropies[2] > RISING_EDGE);
+        assert_eq!(
+            profile.edges(),
+            [EntropyEdge {
+                offset: 2 * 4096,
+                rising: true
+            }]
+        );
+    }
+
+    #[test]
+    fn edge_hysteresis() {
+        let uniform: Vec<u8> = (0..=255).collect();
+        // 128 values twice each, 7 bits between the two thresholds.
+        let between: Vec<u8> = (0..=255).map(|byte: u8| byte / 2).collect();
+        let bytes = [&uniform[..], &between, &[0; 256], &between, &uniform].concat();
+        let profile = profile(bytes, 256);
+        assert_eq!(profile.entropies[1], 7.0);
+        assert_eq!(
+            profile.edges(),
+            [
+                EntropyEdge {
+                    offset: 512,
+                    rising: false
+                },
+                EntropyEdge {
+                    offset: 1024,
+                    rising: true
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn short_last_window() {
+        let mut bytes = vec![0; 3 * 256];
+        bytes.extend(0..10);
+        let profile = profile(bytes, 256);
+        assert_eq!(profile.len, 3 * 256 + 10);
+        assert_eq!(profile.entropies.len(), 4);
+        assert!(close(
+            profile.entropy_at(profile.len - 1).unwrap(),
+            10f64.log2()
+        ));
+        assert_eq!(profile.entropy_at(profile.len + 256), None);
+    }
+
+    #[test]
+    fn cancelled_profile() {
+        let provider = MemoryDataProvider::new(random_bytes(4096));
+        let profile = EntropyProfile::new(&provider, 256, &AtomicBool::new(true), |_| {});
+        assert!(profile.entropies.is_empty());
+        assert!(profile.edges().is_empty());
+        assert_eq!(profile.entropy_at(0), None);
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::DataProvider;
+
+/// Bytes looked at, larger ranges are sampled from their start.
+const SAMPLE_SIZE: usize = 1 << 20;
+/// Match rate above chance a lag needs to be proposed.
+const MIN_SCORE: f64 = 0.02;
+/// A multiple of a proposed stride must beat it by this factor to be
+/// proposed as well.
+const HARMONIC_FACTOR: f64 = 1.1;
+/// Proposals returned at most.
+const MAX_CANDIDATES: usize = 10;
+
+/// Record size proposed by [`find_strides`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stride {
+    pub stride: usize,
+    /// Fraction of bytes equal to the byte one stride further, minus the
+    /// fraction expected from the byte distribution alone.
+    pub score: f64,
+}
+
+/// Proposes record sizes up to `max_stride` for `range`, best first.
+/// Fixed size records repeat their constant fields and padding, so the
+/// bytes correlate with the bytes one record further. `progress` is
+/// called with the number of lags done, nothing is proposed when `cancel`
+/// is set.
+pub fn find_strides<F>(
+    provider: &dyn DataProvider,
+    range: Range<usize>,
+    max_stride: usize,
+    cancel: &AtomicBool,
+    mut progress: F,
+) -> Vec<Stride>
+where
+    F: FnMut(usize),
+{
+    let end = std::cmp::min(range.end, provider.len());
+    let size = std::cmp::min(end.saturating_sub(range.start), SAMPLE_SIZE);
+    let bytes = match provider.get(range.start, size) {
+        Some(bytes) => bytes,
+        None => return Vec::new(),
+    };
+    let max_stride = std::cmp::min(max_stride, bytes.len() / 2);
+    let chance = chance(&bytes);
+    let mut scores = vec![0.0; max_stride + 2];
+    for (lag, score) in scores.iter_mut().enumerate().take(max_stride + 1).skip(1) {
+        if cancel.load(Ordering::Relaxed) {
+            return Vec::new();
+        }
+        *score = match_rate(&bytes, lag) - chance;
+        progress(lag);
+    }
+
+    let mut candidates: Vec<Stride> = Vec::new();
+    for stride in 2..=max_stride {
+        let score = scores[stride];
+        let peak = score >= scores[stride - 1] && score >= scores[stride + 1];
+        if !peak || score < MIN_SCORE {
+            continue;
+        }
+        let harmonic = candidates.iter().any(|candidate| {
+            stride.is_multiple_of(candidate.stride) && score < candidate.score * HARMONIC_FACTOR
+        });
+        if !harmonic {
+            candidates.push(Stride { stride, score });
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.stride.cmp(&b.stride)));
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// Fraction of bytes equal to the byte `lag` further.
+fn match_rate(bytes: &[u8], lag: usize) -> f64 {
+    let pairs = bytes.len() - lag;
+    let matches = bytes[..pairs]
+        .iter()
+        .zip(&bytes[lag..])
+        .filter(|(a, b)| a == b)
+        .count();
+    matches as f64 / pairs.max(1) as f64
+}
+
+/// Probability of two independent bytes of `bytes` being equal.
+fn chance(bytes: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+    let total = bytes.len().max(1) as f64;
+    counts
+        .iter()
+        .map(|&count| (count as f64 / total).powi(2))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryDataProvider;
+
+    #[test]
+    fn fixed_size_records() {
+        // 24 byte records of a counter, a varying checksum and padding.
+        let bytes: Vec<u8> = (0..400u32)
+            .flat_map(|index| {
+                let mut record = vec![0u8; 24];
+                record[..4].copy_from_slice(&index.to_le_bytes());
+                record[4..8].copy_from_slice(&index.wrapping_mul(2654435761).to_le_bytes());
+                record[8..12].copy_from_slice(b"REC\0");
+                record
+            })
+            .collect();
+        let provider = MemoryDataProvider::new(bytes);
+        let mut done = 0;
+        let strides = find_strides(
+            &provider,
+            0..provider.len(),
+            64,
+            &AtomicBool::new(false),
+            |lags| done = lags,
+        );
+        assert_eq!(strides.first().map(|stride| stride.stride), Some(24));
+        assert_eq!(done, 64);
+        let cancelled = find_strides(
+            &provider,
+            0..provider.len(),
+            64,
+            &AtomicBool::new(true),
+            |_| {},
+        );
+        assert!(cancelled.is_empty());
+    }
+}
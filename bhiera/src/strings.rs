@@ -0,0 +1,214 @@
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+
+use crate::chunks::for_each_chunk;
+use crate::DataProvider;
+
+/// Characters kept of a string, the range still covers all of it.
+const MAX_TEXT: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringEncoding {
+    Ascii,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl StringEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StringEncoding::Ascii => "ASCII",
+            StringEncoding::Utf16Le => "UTF-16LE",
+            StringEncoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    fn unit_size(&self) -> usize {
+        match self {
+            StringEncoding::Ascii => 1,
+            StringEncoding::Utf16Le | StringEncoding::Utf16Be => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FoundString {
+    pub range: Range<usize>,
+    pub encoding: StringEncoding,
+    pub text: String,
+}
+
+impl FoundString {
+    /// Length in characters.
+    pub fn len(&self) -> usize {
+        self.range.len() / self.encoding.unit_size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+fn is_printable(byte: u8) -> bool {
+    byte == b'\t' || (0x20..0x7f).contains(&byte)
+}
+
+/// Collects the printable run of one encoding at one alignment.
+struct Scanner {
+    encoding: StringEncoding,
+    alignment: usize,
+    /// First byte of the current UTF-16 unit.
+    previous: Option<u8>,
+    start: Option<usize>,
+    text: String,
+}
+
+impl Scanner {
+    fn new(encoding: StringEncoding, alignment: usize) -> Self {
+        Self {
+            encoding,
+            alignment,
+            previous: None,
+            start: None,
+            text: String::new(),
+        }
+    }
+
+    fn push(&mut self, offset: usize, byte: u8, min_length: usize, found: &mut Vec<FoundString>) {
+        let character = match (self.encoding, self.previous.take()) {
+            (StringEncoding::Ascii, _) => Some(byte),
+            _ if offset % 2 == self.alignment => {
+                self.previous = Some(byte);
+                return;
+            }
+            // The range starts within a unit.
+            (_, None) => return,
+            (StringEncoding::Utf16Le, Some(low)) => (byte == 0).then_some(low),
+            (StringEncoding::Utf16Be, Some(high)) => (high == 0).then_some(byte),
+        };
+        let unit_start = offset + 1 - self.encoding.unit_size();
+        match character.filter(|c| is_printable(*c)) {
+            Some(c) => {
+                self.start.get_or_insert(unit_start);
+                if self.text.len() < MAX_TEXT {
+                    self.text.push(c as char);
+                }
+            }
+            None => self.finish(unit_start, min_length, found),
+        }
+    }
+
+    fn finish(&mut self, end: usize, min_length: usize, found: &mut Vec<FoundString>) {
+        if let Some(start) = self.start.take() {
+            if (end - start) / self.encoding.unit_size() >= min_length {
+                found.push(FoundString {
+                    range: start..end,
+                    encoding: self.encoding,
+                    text: std::mem::take(&mut self.text),
+                });
+            }
+        }
+        self.text.clear();
+    }
+}
+
+/// Extracts ASCII and UTF-16 strings of at least `min_length` characters
+/// from `range`, ordered by offset. Strings found until `cancel` is set
+/// are returned.
+pub fn find_strings<F>(
+    provider: &dyn DataProvider,
+    range: Range<usize>,
+    min_length: usize,
+    cancel: &AtomicBool,
+    progress: F,
+) -> Vec<FoundString>
+where
+    F: FnMut(usize),
+{
+    let min_length = min_length.max(1);
+    let mut scanners = [
+        Scanner::new(StringEncoding::Ascii, 0),
+        Scanner::new(StringEncoding::Utf16Le, 0),
+        Scanner::new(StringEncoding::Utf16Le, 1),
+        Scanner::new(StringEncoding::Utf16Be, 0),
+        Scanner::new(StringEncoding::Utf16Be, 1),
+    ];
+    let mut found = Vec::new();
+    let end = std::cmp::min(range.end, provider.len());
+    for_each_chunk(provider, range, cancel, progress, |offset, bytes| {
+        for (i, byte) in bytes.iter().enumerate() {
+            for scanner in scanners.iter_mut() {
+                scanner.push(offset + i, *byte, min_length, &mut found);
+            }
+        }
+    });
+    for scanner in scanners.iter_mut() {
+        // A trailing UTF-16 string ends at its last complete unit.
+        let end = scanner.start.map_or(end, |start| {
+            start + (end - start) / scanner.encoding.unit_size() * scanner.encoding.unit_size()
+        });
+        scanner.finish(end, min_length, &mut found);
+    }
+    found.sort_by_key(|string| string.range.start);
+    drop_shifted(found)
+}
+
+/// ASCII text in UTF-16 shows up in both byte orders, shifted by one byte.
+/// Keeps the longer one of overlapping UTF-16 strings.
+fn drop_shifted(found: Vec<FoundString>) -> Vec<FoundString> {
+    let mut kept: Vec<FoundString> = Vec::with_capacity(found.len());
+    let mut last_utf16: Option<usize> = None;
+    for string in found {
+        if string.encoding == StringEncoding::Ascii {
+            kept.push(string);
+            continue;
+        }
+        match last_utf16 {
+            Some(index) if kept[index].range.end > string.range.start => {
+                if string.range.len() > kept[index].range.len() {
+                    kept[index] = string;
+                }
+            }
+            _ => {
+                last_utf16 = Some(kept.len());
+                kept.push(string);
+            }
+        }
+    }
+    // A kept string may start behind ASCII strings found in between.
+    kept.sort_by_key(|string| string.range.start);
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(range: Range<usize>, encoding: StringEncoding) -> FoundString {
+        FoundString {
+            range,
+            encoding,
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn drop_shifted_keeps_order() {
+        let kept = drop_shifted(vec![
+            string(0..8, StringEncoding::Utf16Le),
+            string(2..6, StringEncoding::Ascii),
+            string(5..30, StringEncoding::Utf16Be),
+            string(40..44, StringEncoding::Ascii),
+        ]);
+        let kept: Vec<_> = kept.iter().map(|string| string.range.clone()).collect();
+        assert_eq!(kept, [2..6, 5..30, 40..44]);
+    }
+
+    #[test]
+    fn utf16_in_both_byte_orders() {
+        let provider = crate::MemoryDataProvider::new(b"\0H\0e\0l\0l\0o\0".to_vec());
+        let found = find_strings(&provider, 0..11, 4, &AtomicBool::new(false), |_| {});
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "Hello");
+    }
+}
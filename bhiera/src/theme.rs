@@ -0,0 +1,177 @@
+use std::fs;
+use std::path::Path;
+
+use crate::{ByteClass, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub background: (u8, u8, u8),
+    pub foreground: (u8, u8, u8),
+    pub offset_background: (u8, u8, u8),
+    pub offset_text: (u8, u8, u8),
+    pub selection: (u8, u8, u8),
+    pub highlight: (u8, u8, u8),
+    pub null: (u8, u8, u8),
+    pub full: (u8, u8, u8),
+    pub whitespace: (u8, u8, u8),
+    pub printable: (u8, u8, u8),
+    pub control: (u8, u8, u8),
+    pub high: (u8, u8, u8),
+    pub gradient_low: (u8, u8, u8),
+    pub gradient_high: (u8, u8, u8),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            background: (255, 255, 255),
+            foreground: (0, 0, 0),
+            offset_background: (224, 224, 224),
+            offset_text: (117, 117, 117),
+            selection: (0, 220, 220),
+            highlight: (255, 230, 0),
+            null: (160, 160, 160),
+            full: (96, 96, 200),
+            whitespace: (0, 140, 0),
+            printable: (0, 128, 128),
+            control: (200, 100, 0),
+            high: (176, 0, 176),
+            gradient_low: (0, 0, 200),
+            gradient_high: (220, 0, 0),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            background: (30, 30, 30),
+            foreground: (212, 212, 212),
+            offset_background: (45, 45, 45),
+            offset_text: (133, 133, 133),
+            selection: (38, 79, 120),
+            highlight: (110, 90, 0),
+            null: (100, 100, 100),
+            full: (130, 150, 255),
+            whitespace: (110, 200, 110),
+            printable: (80, 210, 210),
+            control: (230, 150, 60),
+            high: (220, 110, 220),
+            gradient_low: (60, 90, 255),
+            gradient_high: (255, 90, 60),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            background: (0, 0, 0),
+            foreground: (255, 255, 255),
+            offset_background: (0, 0, 0),
+            offset_text: (255, 255, 0),
+            selection: (0, 0, 255),
+            highlight: (128, 0, 128),
+            null: (128, 128, 128),
+            full: (0, 255, 255),
+            whitespace: (0, 255, 0),
+            printable: (255, 255, 255),
+            control: (255, 128, 0),
+            high: (255, 0, 255),
+            gradient_low: (0, 128, 255),
+            gradient_high: (255, 255, 0),
+        }
+    }
+
+    pub fn builtin() -> Vec<(String, Theme)> {
+        vec![
+            ("light".into(), Theme::light()),
+            ("dark".into(), Theme::dark()),
+            ("high-contrast".into(), Theme::high_contrast()),
+        ]
+    }
+
+    pub fn class_color(&self, class: ByteClass) -> (u8, u8, u8) {
+        match class {
+            ByteClass::Null => self.null,
+            ByteClass::Full => self.full,
+            ByteClass::Whitespace => self.whitespace,
+            ByteClass::Printable => self.printable,
+            ByteClass::Control => self.control,
+            ByteClass::High => self.high,
+        }
+    }
+
+    /// Loads user themes from a TOML file with one table per theme:
+    ///
+    /// ```toml
+    /// [themes.solarized]
+    /// base = "dark"
+    /// background = "#002b36"
+    /// ```
+    ///
+    /// Colors that are not given are taken from the `base` theme, which
+    /// defaults to "light".
+    pub fn load(path: &Path) -> Result<Vec<(String, Theme)>> {
+        let config: toml::Table = fs::read_to_string(path)?.parse()?;
+        let mut themes = Vec::new();
+        let tables = match config.get("themes").and_then(|themes| themes.as_table()) {
+            Some(tables) => tables,
+            None => return Ok(themes),
+        };
+        for (name, table) in tables {
+            let table = table
+                .as_table()
+                .ok_or_else(|| anyhow::anyhow!("theme {} is not a table", name))?;
+            let mut theme = match table.get("base").and_then(|base| base.as_str()) {
+                Some("dark") => Theme::dark(),
+                Some("high-contrast") => Theme::high_contrast(),
+                _ => Theme::light(),
+            };
+            for (key, value) in table.iter().filter(|(key, _)| *key != "base") {
+                let color = value
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("{}.{} is not a string", name, key))
+                    .and_then(parse_color)?;
+                *theme
+                    .color_mut(key)
+                    .ok_or_else(|| anyhow::anyhow!("unknown theme color: {}.{}", name, key))? =
+                    color;
+            }
+            themes.push((name.clone(), theme));
+        }
+        Ok(themes)
+    }
+
+    fn color_mut(&mut self, key: &str) -> Option<&mut (u8, u8, u8)> {
+        let color = match key {
+            "background" => &mut self.background,
+            "foreground" => &mut self.foreground,
+            "offset_background" => &mut self.offset_background,
+            "offset_text" => &mut self.offset_text,
+            "selection" => &mut self.selection,
+            "highlight" => &mut self.highlight,
+            "null" => &mut self.null,
+            "full" => &mut self.full,
+            "whitespace" => &mut self.whitespace,
+            "printable" => &mut self.printable,
+            "control" => &mut self.control,
+            "high" => &mut self.high,
+            "gradient_low" => &mut self.gradient_low,
+            "gradient_high" => &mut self.gradient_high,
+            _ => return None,
+        };
+        Some(color)
+    }
+}
+
+fn parse_color(text: &str) -> Result<(u8, u8, u8)> {
+    let hex = text.trim_start_matches('#');
+    if hex.len() != 6 {
+        anyhow::bail!("invalid color: {}", text);
+    }
+    let value = u32::from_str_radix(hex, 16)?;
+    Ok(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
@@ -2,12 +2,14 @@ use std::sync::{Arc, RwLock};
 
 use bhiera::Bhiera;
 use slint::ComponentHandle;
+use tokio::runtime::Runtime;
 
 use crate::GbhieraUI;
 
 pub struct GbhieraApp {
     bhiera: Arc<RwLock<Bhiera>>,
     ui: GbhieraUI,
+    runtime: Runtime,
 }
 
 impl GbhieraApp {
@@ -15,11 +17,12 @@ impl GbhieraApp {
         Self {
             bhiera: Arc::new(RwLock::new(Bhiera::new())),
             ui,
+            runtime: Runtime::new().unwrap(),
         }
     }
 
     pub fn run(&self) {
-        crate::ui::setup(&self.ui, self.bhiera.clone());
+        crate::ui::setup(&self.ui, self.bhiera.clone(), self.runtime.handle().clone());
         self.ui.run().unwrap();
     }
 }
@@ -0,0 +1,153 @@
+use std::sync::{Arc, RwLock};
+
+use bhiera::{export_annotations, import_annotations, Bhiera, Model};
+use slint::{Color, ComponentHandle, ModelRc, SharedString, VecModel};
+
+use super::bookmarks::{palette_color, save};
+use super::ui::{refresh, scroll_to_cursor, update_status};
+use crate::{AnnotationItem, GbhieraUI};
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let instance = bhiera.clone();
+    ui.on_element_info({
+        move |_revision, _selection_revision| element_info(&instance.read().unwrap()).into()
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_add_annotation({
+        move |label, comment, color| {
+            let mut bhiera = instance.write().unwrap();
+            if bhiera.data_provider().is_none() {
+                return;
+            }
+            let label = match label.is_empty() {
+                true => bhiera.cursor_address(),
+                false => label.into(),
+            };
+            bhiera.add_annotation(label, comment.into(), palette_color(&color));
+            save(&handle_weak, &bhiera);
+            update_annotations(&handle_weak, &bhiera);
+            refresh(&handle_weak);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_annotation_clicked({
+        move |index| {
+            let mut bhiera = instance.write().unwrap();
+            if let Some(annotation) = bhiera.annotations().get(index as usize) {
+                let range = annotation.range.clone();
+                bhiera.jump(range);
+                scroll_to_cursor(&handle_weak, instance.clone());
+                refresh(&handle_weak);
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_remove_annotation({
+        move |index| {
+            let mut bhiera = instance.write().unwrap();
+            bhiera.remove_annotation(index as usize);
+            save(&handle_weak, &bhiera);
+            update_annotations(&handle_weak, &bhiera);
+            refresh(&handle_weak);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_import_annotations({
+        move || {
+            let path = match rfd::FileDialog::new()
+                .set_title("Import annotations")
+                .add_filter("JSON", &["json"])
+                .pick_file()
+            {
+                Some(path) => path,
+                None => return,
+            };
+            match import_annotations(&path) {
+                Ok(annotations) => {
+                    let count = annotations.len();
+                    let mut bhiera = instance.write().unwrap();
+                    bhiera.extend_annotations(annotations);
+                    save(&handle_weak, &bhiera);
+                    update_annotations(&handle_weak, &bhiera);
+                    update_status(&handle_weak, format!("Imported {} annotations", count));
+                    refresh(&handle_weak);
+                }
+                Err(err) => update_status(&handle_weak, format!("Import failed: {}", err)),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_export_annotations({
+        move || {
+            let path = match rfd::FileDialog::new()
+                .set_title("Export annotations")
+                .add_filter("JSON", &["json"])
+                .save_file()
+            {
+                Some(path) => path,
+                None => return,
+            };
+            match export_annotations(&path, instance.read().unwrap().annotations()) {
+                Ok(_) => update_status(&handle_weak, format!("Exported {}", path.display())),
+                Err(err) => update_status(&handle_weak, format!("Export failed: {}", err)),
+            }
+        }
+    });
+}
+
+/// Label, range and comment of the annotations under the cursor.
+fn element_info(bhiera: &Bhiera) -> String {
+    let address_mode = bhiera.address_mode();
+    bhiera
+        .annotations_at(bhiera.cursor())
+        .iter()
+        .map(|annotation| {
+            format!(
+                "{}\n{} - {}\n\n{}",
+                annotation.label,
+                address_mode.format(annotation.range.start),
+                address_mode.format(annotation.range.end - 1),
+                annotation.comment
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Rebuilds the annotation list, e.g. after loading or when ranges moved.
+pub(super) fn update_annotations(handle: &slint::Weak<GbhieraUI>, bhiera: &Bhiera) {
+    let address_mode = bhiera.address_mode();
+    let annotations: Vec<(String, String, (u8, u8, u8))> = bhiera
+        .annotations()
+        .iter()
+        .map(|annotation| {
+            (
+                annotation.label.clone(),
+                format!(
+                    "{}+{:X}",
+                    address_mode.format(annotation.range.start),
+                    annotation.range.len()
+                ),
+                annotation.color,
+            )
+        })
+        .collect();
+    handle
+        .upgrade_in_event_loop(move |h| {
+            let annotations: Vec<AnnotationItem> = annotations
+                .into_iter()
+                .map(|(label, range, (r, g, b))| AnnotationItem {
+                    label: SharedString::from(label),
+                    range: range.into(),
+                    color: Color::from_rgb_u8(r, g, b),
+                })
+                .collect();
+            h.set_annotations(ModelRc::new(VecModel::from(annotations)));
+        })
+        .unwrap();
+}
@@ -0,0 +1,146 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{content_hash, Bhiera, Model};
+use slint::{Color, ComponentHandle, ModelRc, SharedString, VecModel};
+use tokio::runtime::Handle;
+
+use super::annotations::update_annotations;
+use super::job::Job;
+use super::ui::{refresh, scroll_to_cursor, update_status};
+use crate::{BookmarkItem, GbhieraUI};
+
+const COLORS: [(&str, (u8, u8, u8)); 6] = [
+    ("red", (230, 50, 50)),
+    ("orange", (240, 150, 20)),
+    ("yellow", (230, 210, 0)),
+    ("green", (40, 180, 70)),
+    ("blue", (50, 110, 240)),
+    ("purple", (160, 70, 200)),
+];
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let colors: Vec<SharedString> = COLORS.iter().map(|(name, _)| (*name).into()).collect();
+    ui.set_bookmark_colors(ModelRc::new(VecModel::from(colors)));
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_add_bookmark({
+        move |name, color| {
+            let mut bhiera = instance.write().unwrap();
+            if bhiera.data_provider().is_none() {
+                return;
+            }
+            let color = palette_color(&color);
+            let name = match name.is_empty() {
+                true => bhiera.cursor_address(),
+                false => name.into(),
+            };
+            bhiera.add_bookmark(name, color);
+            save(&handle_weak, &bhiera);
+            update_bookmarks(&handle_weak, &bhiera);
+            refresh(&handle_weak);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_bookmark_clicked({
+        move |index| {
+            let mut bhiera = instance.write().unwrap();
+            if let Some(bookmark) = bhiera.bookmarks().get(index as usize) {
+                let offset = bookmark.offset;
+                update_status(&handle_weak, format!("Bookmark {}", bookmark.name));
+                bhiera.jump(offset..offset);
+                scroll_to_cursor(&handle_weak, instance.clone());
+                refresh(&handle_weak);
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_remove_bookmark({
+        move |index| {
+            let mut bhiera = instance.write().unwrap();
+            bhiera.remove_bookmark(index as usize);
+            save(&handle_weak, &bhiera);
+            update_bookmarks(&handle_weak, &bhiera);
+            refresh(&handle_weak);
+        }
+    });
+}
+
+/// Looks up a color of the bookmark palette, which annotations share.
+pub(super) fn palette_color(name: &str) -> (u8, u8, u8) {
+    COLORS
+        .iter()
+        .find(|(color_name, _)| *color_name == name)
+        .map_or(COLORS[0].1, |(_, color)| *color)
+}
+
+pub(super) fn save(handle: &slint::Weak<GbhieraUI>, bhiera: &Bhiera) {
+    if let Err(err) = bhiera.save_notes() {
+        update_status(handle, format!("Cannot save notes: {}", err));
+    }
+}
+
+/// Hashes the document in the background, which finds the notes of a
+/// renamed document. With `save_notes` the notes are stored under the new
+/// hash.
+pub(super) fn hash_document(
+    handle: &slint::Weak<GbhieraUI>,
+    bhiera: Arc<RwLock<Bhiera>>,
+    runtime: &Handle,
+    job: &Job,
+    save_notes: bool,
+) {
+    let provider = match bhiera.read().unwrap().snapshot() {
+        Some(provider) => provider,
+        None => return,
+    };
+    let cancel = job.start();
+    let handle_weak = handle.clone();
+    runtime.spawn_blocking(move || {
+        let sha256 = content_hash(provider.as_ref(), &cancel);
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut instance = bhiera.write().unwrap();
+        if let Err(err) = instance.set_content_hash(&provider, sha256) {
+            update_status(&handle_weak, format!("Cannot load bookmarks: {}", err));
+        }
+        if save_notes {
+            save(&handle_weak, &instance);
+        }
+        update_bookmarks(&handle_weak, &instance);
+        update_annotations(&handle_weak, &instance);
+        refresh(&handle_weak);
+    });
+}
+
+/// Rebuilds the bookmark list, e.g. after loading or when offsets moved.
+pub(super) fn update_bookmarks(handle: &slint::Weak<GbhieraUI>, bhiera: &Bhiera) {
+    let address_mode = bhiera.address_mode();
+    let bookmarks: Vec<(String, String, (u8, u8, u8))> = bhiera
+        .bookmarks()
+        .iter()
+        .map(|bookmark| {
+            (
+                bookmark.name.clone(),
+                address_mode.format(bookmark.offset),
+                bookmark.color,
+            )
+        })
+        .collect();
+    handle
+        .upgrade_in_event_loop(move |h| {
+            let bookmarks: Vec<BookmarkItem> = bookmarks
+                .into_iter()
+                .map(|(name, address, (r, g, b))| BookmarkItem {
+                    name: name.into(),
+                    address: address.into(),
+                    color: Color::from_rgb_u8(r, g, b),
+                })
+                .collect();
+            h.set_bookmarks(ModelRc::new(VecModel::from(bookmarks)));
+        })
+        .unwrap();
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{checksum_matches, checksums, Bhiera, Model};
+use slint::{ComponentHandle, Model as _, ModelRc, VecModel};
+use tokio::runtime::Handle;
+
+use super::copy_to_clipboard;
+use super::job::Job;
+use super::ui::update_status;
+use crate::{ChecksumRow, GbhieraUI};
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let job = Job::default();
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_compute_checksums({
+        move || {
+            let bhiera = instance.read().unwrap();
+            let provider = match bhiera.snapshot() {
+                Some(provider) => provider,
+                None => return,
+            };
+            let range = bhiera.selection_or_document();
+            let address_mode = bhiera.address_mode();
+            let description = format!(
+                "{}..{} ({} bytes)",
+                address_mode.format(range.start),
+                address_mode.format(range.end),
+                range.len()
+            );
+            drop(bhiera);
+            let cancel = job.start();
+            let handle_weak = handle_weak.clone();
+            runtime.spawn_blocking(move || {
+                let total = range.len();
+                let results = checksums(provider.as_ref(), range, &cancel, |done| {
+                    let percent = done * 100 / total.max(1);
+                    update_status(&handle_weak, format!("Computing checksums... {}%", percent));
+                });
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                update_status(
+                    &handle_weak,
+                    format!("Computed checksums of {}", description),
+                );
+                handle_weak
+                    .upgrade_in_event_loop(move |h| {
+                        let expected = h.get_checksum_expected();
+                        let rows: Vec<ChecksumRow> = results
+                            .into_iter()
+                            .map(|(kind, value)| ChecksumRow {
+                                name: kind.name().into(),
+                                matched: checksum_matches(&value, &expected),
+                                value: value.into(),
+                            })
+                            .collect();
+                        h.set_checksums(ModelRc::new(VecModel::from(rows)));
+                        h.set_checksum_range(description.into());
+                    })
+                    .unwrap();
+            });
+        }
+    });
+    let handle_weak = ui.as_weak();
+    ui.on_compare_checksums({
+        move |expected| {
+            let handle = match handle_weak.upgrade() {
+                Some(handle) => handle,
+                None => return,
+            };
+            let rows: Vec<ChecksumRow> = handle
+                .get_checksums()
+                .iter()
+                .map(|row| ChecksumRow {
+                    matched: checksum_matches(&row.value, &expected),
+                    ..row
+                })
+                .collect();
+            let matches: Vec<String> = rows
+                .iter()
+                .filter(|row| row.matched)
+                .map(|row| row.name.to_string())
+                .collect();
+            handle.set_checksums(ModelRc::new(VecModel::from(rows)));
+            match (expected.trim().is_empty(), matches.is_empty()) {
+                (true, _) => {}
+                (false, true) => update_status(&handle_weak, "No checksum matches"),
+                (false, false) => {
+                    update_status(&handle_weak, format!("Matches {}", matches.join(", ")))
+                }
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    ui.on_copy_checksum({
+        move |value| match copy_to_clipboard(value.as_str()) {
+            Ok(_) => update_status(&handle_weak, format!("Copied {}", value)),
+            Err(err) => update_status(&handle_weak, format!("Copy failed: {}", err)),
+        }
+    });
+}
@@ -0,0 +1,7 @@
+pub fn copy_to_clipboard<S>(text: S) -> Result<(), arboard::Error>
+where
+    S: Into<String>,
+{
+    let mut clipboard = arboard::Clipboard::new()?;
+    clipboard.set_text(text.into())
+}
@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+use bhiera::{disassemble, AddressMode, Bhiera, Bitness, DecodedInstruction, Model};
+use slint::{ComponentHandle, ModelRc, VecModel};
+
+use super::ui::{refresh, scroll_to_cursor};
+use crate::{GbhieraUI, InstructionRow};
+
+/// Instructions decoded at once.
+const INSTRUCTIONS: usize = 256;
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let decoded: Arc<Mutex<Vec<DecodedInstruction>>> = Arc::new(Mutex::new(Vec::new()));
+    let instance = bhiera.clone();
+    let instructions = decoded.clone();
+    ui.on_disassemble({
+        move |_revision, _selection_revision, bitness| {
+            let bhiera = instance.read().unwrap();
+            let provider = match bhiera.data_provider() {
+                Some(provider) => provider,
+                None => return ModelRc::default(),
+            };
+            let bitness: Bitness = bitness.parse().unwrap_or_default();
+            let position = bhiera.selection().start;
+            let mut instructions = instructions.lock().unwrap();
+            // Keep decoding from the same start while the cursor stays within
+            // the listed instructions, so the list doesn't jump around.
+            let start = match (instructions.first(), instructions.last()) {
+                (Some(first), Some(last))
+                    if (first.range.start..last.range.end).contains(&position) =>
+                {
+                    first.range.start
+                }
+                _ => position,
+            };
+            let address_mode = bhiera.address_mode();
+            *instructions = disassemble(provider, start, INSTRUCTIONS, bitness, address_mode);
+            let rows: Vec<InstructionRow> = instructions
+                .iter()
+                .map(|instruction| InstructionRow {
+                    address: address_mode.format(instruction.range.start).into(),
+                    bytes: provider
+                        .get(instruction.range.start, instruction.range.len())
+                        .map(|bytes| {
+                            bytes
+                                .iter()
+                                .map(|byte| format!("{:02X}", byte))
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        })
+                        .unwrap_or_default()
+                        .into(),
+                    text: instruction.text.clone().into(),
+                    target: instruction
+                        .target
+                        .map(|target| address_mode.format(target))
+                        .unwrap_or_default()
+                        .into(),
+                    current: instruction.range.contains(&position),
+                })
+                .collect();
+            ModelRc::new(VecModel::from(rows))
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let instructions = decoded.clone();
+    ui.on_instruction_clicked({
+        move |address| {
+            let address_mode = instance.read().unwrap().address_mode();
+            let range = match find(&instructions.lock().unwrap(), address_mode, &address) {
+                Some(instruction) => instruction.range.clone(),
+                None => return,
+            };
+            instance.write().unwrap().select(range);
+            scroll_to_cursor(&handle_weak, instance.clone());
+            refresh(&handle_weak);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_target_clicked({
+        move |address| {
+            let address_mode = instance.read().unwrap().address_mode();
+            let target = find(&decoded.lock().unwrap(), address_mode, &address)
+                .and_then(|instruction| instruction.target);
+            if let Some(target) = target {
+                instance.write().unwrap().jump(target..target);
+                scroll_to_cursor(&handle_weak, instance.clone());
+                refresh(&handle_weak);
+            }
+        }
+    });
+}
+
+/// The listed instruction at the formatted `address`.
+fn find<'a>(
+    instructions: &'a [DecodedInstruction],
+    address_mode: AddressMode,
+    address: &str,
+) -> Option<&'a DecodedInstruction> {
+    instructions
+        .iter()
+        .find(|instruction| address_mode.format(instruction.range.start) == address)
+}
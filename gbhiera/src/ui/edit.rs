@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{Bhiera, Model};
+use slint::ComponentHandle;
+use tokio::runtime::Handle;
+
+use super::annotations::update_annotations;
+use super::bookmarks::{hash_document, update_bookmarks};
+use super::job::Job;
+use super::results::update_results;
+use super::ui::{refresh, resize, update_status};
+use crate::GbhieraUI;
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_undo({
+        move || {
+            let result = instance.write().unwrap().undo();
+            match result {
+                Ok(true) => {
+                    update_status(&handle_weak, "Undone");
+                    edited(&handle_weak, instance.clone());
+                }
+                Ok(false) => update_status(&handle_weak, "Nothing to undo"),
+                Err(err) => update_status(&handle_weak, format!("Undo failed: {}", err)),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_redo({
+        move || {
+            let result = instance.write().unwrap().redo();
+            match result {
+                Ok(true) => {
+                    update_status(&handle_weak, "Redone");
+                    edited(&handle_weak, instance.clone());
+                }
+                Ok(false) => update_status(&handle_weak, "Nothing to redo"),
+                Err(err) => update_status(&handle_weak, format!("Redo failed: {}", err)),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let job = Job::default();
+    ui.on_save({
+        move || {
+            let path = match handle_weak.upgrade() {
+                Some(handle) => PathBuf::from(handle.get_binary_path().as_str()),
+                None => return,
+            };
+            let bhiera = instance.read().unwrap();
+            match bhiera.save(&path).and_then(|_| bhiera.save_notes()) {
+                Ok(_) => {
+                    update_status(&handle_weak, format!("Saved {}", path.display()));
+                    drop(bhiera);
+                    // The notes belong to the saved content from now on.
+                    hash_document(&handle_weak, instance.clone(), &runtime, &job, true);
+                }
+                Err(err) => update_status(&handle_weak, format!("Save failed: {}", err)),
+            }
+        }
+    });
+}
+
+/// Updates everything that shows document offsets after an edit.
+pub(super) fn edited(handle: &slint::Weak<GbhieraUI>, bhiera: Arc<RwLock<Bhiera>>) {
+    let instance = bhiera.read().unwrap();
+    update_results(handle, &instance);
+    update_bookmarks(handle, &instance);
+    update_annotations(handle, &instance);
+    drop(instance);
+    resize(handle, bhiera);
+    refresh(handle);
+}
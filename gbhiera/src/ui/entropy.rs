@@ -0,0 +1,198 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{Bhiera, EntropyProfile, Model, Theme};
+use plotters::prelude::*;
+use slint::{ComponentHandle, ModelRc, Rgb8Pixel, SharedPixelBuffer, VecModel};
+use tokio::runtime::Handle;
+
+use super::job::{Computed, Job};
+use super::ui::{refresh, scroll_to_cursor, update_status};
+use crate::{EntropyEdgeItem, GbhieraUI};
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let job = Job::default();
+    let computed: Computed<EntropyProfile> = Computed::default();
+    let instance = bhiera.clone();
+    let profile = computed.clone();
+    ui.on_render_entropy({
+        move |width, height, edges, _revision| {
+            let bhiera = instance.read().unwrap();
+            let size = (width.max(0) as u32, height.max(0) as u32);
+            profile
+                .with(&bhiera, |profile| {
+                    render_entropy(profile, &bhiera.theme(), size, edges)
+                })
+                .unwrap_or_default()
+        }
+    });
+    let instance = bhiera.clone();
+    let profile = computed.clone();
+    ui.on_entropy_info({
+        move |position| {
+            let bhiera = instance.read().unwrap();
+            profile
+                .with(&bhiera, |profile| {
+                    let offset = offset_at(profile, position);
+                    profile.entropy_at(offset).map(|entropy| {
+                        let address = bhiera.address_mode().format(offset);
+                        format!("{}: {:.3} bits per byte", address, entropy)
+                    })
+                })
+                .flatten()
+                .unwrap_or_default()
+                .into()
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let profile = computed.clone();
+    ui.on_compute_entropy({
+        move |window| {
+            let window = match window.parse::<usize>() {
+                Ok(window) => window,
+                Err(err) => {
+                    update_status(&handle_weak, format!("Invalid window: {}", err));
+                    return;
+                }
+            };
+            let provider = match instance.read().unwrap().snapshot() {
+                Some(provider) => provider,
+                None => return,
+            };
+            let cancel = job.start();
+            let handle_weak = handle_weak.clone();
+            let instance = instance.clone();
+            let profile = profile.clone();
+            runtime.spawn_blocking(move || {
+                let total = provider.len();
+                let computed = EntropyProfile::new(provider.as_ref(), window, &cancel, |done| {
+                    let percent = done * 100 / total.max(1);
+                    update_status(&handle_weak, format!("Computing entropy... {}%", percent));
+                });
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                let address_mode = instance.read().unwrap().address_mode();
+                let edges: Vec<(String, bool)> = computed
+                    .edges()
+                    .iter()
+                    .map(|edge| (address_mode.format(edge.offset), edge.rising))
+                    .collect();
+                update_status(
+                    &handle_weak,
+                    format!("Computed entropy, {} boundaries", edges.len()),
+                );
+                profile.set(provider, computed);
+                handle_weak
+                    .upgrade_in_event_loop(move |h| {
+                        let edges: Vec<EntropyEdgeItem> = edges
+                            .into_iter()
+                            .map(|(address, rising)| EntropyEdgeItem {
+                                address: address.into(),
+                                rising,
+                            })
+                            .collect();
+                        h.set_entropy_edges(ModelRc::new(VecModel::from(edges)));
+                        h.set_entropy_revision(h.get_entropy_revision() + 1);
+                    })
+                    .unwrap();
+            });
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let profile = computed.clone();
+    ui.on_entropy_clicked({
+        move |position| {
+            let offset = profile.with(&instance.read().unwrap(), |profile| {
+                offset_at(profile, position)
+            });
+            if let Some(offset) = offset {
+                jump(&handle_weak, instance.clone(), offset);
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_edge_clicked({
+        move |index| {
+            let edge = computed.with(&instance.read().unwrap(), |profile| {
+                profile.edges().get(index as usize).copied()
+            });
+            if let Some(edge) = edge.flatten() {
+                jump(&handle_weak, instance.clone(), edge.offset);
+            }
+        }
+    });
+}
+
+fn offset_at(profile: &EntropyProfile, position: f32) -> usize {
+    let offset = (position.clamp(0.0, 1.0) as f64 * profile.len as f64) as usize;
+    offset.min(profile.len.saturating_sub(1))
+}
+
+fn jump(handle: &slint::Weak<GbhieraUI>, bhiera: Arc<RwLock<Bhiera>>, offset: usize) {
+    bhiera.write().unwrap().jump(offset..offset);
+    scroll_to_cursor(handle, bhiera);
+    refresh(handle);
+    handle
+        .upgrade_in_event_loop(|h| h.set_current_tab(0))
+        .unwrap();
+}
+
+fn render_entropy(
+    profile: &EntropyProfile,
+    theme: &Theme,
+    size: (u32, u32),
+    edges: bool,
+) -> slint::Image {
+    if size.0 == 0 || size.1 == 0 {
+        return slint::Image::default();
+    }
+    let mut pixel_buffer = SharedPixelBuffer::<Rgb8Pixel>::new(size.0, size.1);
+    let backend = BitMapBackend::with_buffer(pixel_buffer.make_mut_bytes(), size);
+    match draw_entropy(backend.into_drawing_area(), profile, theme, edges) {
+        Ok(_) => slint::Image::from_rgb8(pixel_buffer),
+        Err(_) => slint::Image::default(),
+    }
+}
+
+/// Draws the range of entropies falling into each column, 8 bits per byte
+/// at the top.
+fn draw_entropy<DB: DrawingBackend>(
+    area: DrawingArea<DB, plotters::coord::Shift>,
+    profile: &EntropyProfile,
+    theme: &Theme,
+    edges: bool,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let color = |(r, g, b): (u8, u8, u8)| RGBColor(r, g, b);
+    area.fill(&color(theme.background))?;
+    let (width, height) = area.dim_in_pixel();
+    let count = profile.entropies.len();
+    if count == 0 {
+        return area.present();
+    }
+    let y = |entropy: f64| ((1.0 - entropy / 8.0) * (height - 1) as f64) as i32;
+    for x in 0..width as usize {
+        let start = x * count / width as usize;
+        let end = ((x + 1) * count / width as usize).max(start + 1).min(count);
+        let column = &profile.entropies[start.min(count - 1)..end];
+        let low = column.iter().copied().fold(f64::MAX, f64::min);
+        let high = column.iter().copied().fold(f64::MIN, f64::max);
+        area.draw(&PathElement::new(
+            vec![(x as i32, y(low)), (x as i32, y(high))],
+            color(theme.foreground),
+        ))?;
+    }
+    if edges {
+        for edge in profile.edges() {
+            let x = (edge.offset as f64 / profile.len as f64 * width as f64) as i32;
+            area.draw(&PathElement::new(
+                vec![(x, 0), (x, height as i32)],
+                color(theme.highlight),
+            ))?;
+        }
+    }
+    area.present()
+}
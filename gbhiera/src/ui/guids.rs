@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use bhiera::{Bhiera, Guid, GuidDatabase, Model, Result};
+use slint::ComponentHandle;
+
+use super::ui::{refresh, update_status};
+use crate::GbhieraUI;
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let handle_weak = ui.as_weak();
+    if let Some(path) = guids_path().filter(|path| path.exists()) {
+        if let Err(err) = import(&mut bhiera.write().unwrap(), &path) {
+            update_status(
+                &handle_weak,
+                format!("Loading {} failed: {}", path.display(), err),
+            );
+        }
+    }
+    let instance = bhiera.clone();
+    ui.on_hover_info({
+        move |(x, y)| {
+            let bhiera = instance.read().unwrap();
+            match bhiera.guid_at(bhiera.byte_at(x, y)) {
+                Some((range, name)) => {
+                    let guid = bhiera
+                        .data_provider()
+                        .and_then(|provider| provider.get(range.start, range.len()))
+                        .and_then(|bytes| Guid::from_bytes(&bytes))
+                        .map(|guid| guid.to_string())
+                        .unwrap_or_default();
+                    format!("{}\n{}", name, guid).into()
+                }
+                None => "".into(),
+            }
+        }
+    });
+    ui.on_import_guids({
+        move || {
+            let path = match rfd::FileDialog::new()
+                .set_title("Import GUIDs")
+                .add_filter("EDK2 package declaration", &["dec"])
+                .add_filter("GUID names", &["toml"])
+                .pick_file()
+            {
+                Some(path) => path,
+                None => return,
+            };
+            match import(&mut bhiera.write().unwrap(), &path) {
+                Ok(count) => {
+                    update_status(
+                        &handle_weak,
+                        format!("Imported {} GUIDs from {}", count, path.display()),
+                    );
+                    refresh(&handle_weak);
+                }
+                Err(err) => update_status(&handle_weak, format!("Import failed: {}", err)),
+            }
+        }
+    });
+}
+
+/// Adds the GUIDs of a `.dec` file or a GUID name file.
+fn import(bhiera: &mut Bhiera, path: &Path) -> Result<usize> {
+    let guids = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("dec") => GuidDatabase::load_dec(path)?,
+        _ => GuidDatabase::load(path)?,
+    };
+    Ok(bhiera.import_guids(guids))
+}
+
+fn guids_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("gbhiera").join("guids.toml"))
+}
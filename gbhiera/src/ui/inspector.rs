@@ -0,0 +1,95 @@
+use std::sync::{Arc, RwLock};
+
+use bhiera::{Bhiera, Edit, Error, Model, Result, ValueKind, GUID_SIZE};
+use slint::{ComponentHandle, ModelRc, VecModel};
+
+use super::edit::edited;
+use super::ui::update_status;
+use crate::{GbhieraUI, InspectorRow};
+
+/// Enough bytes for the largest value, a GUID or a 10 byte LEB128.
+const INSPECTED_BYTES: usize = 16;
+/// Row naming the GUID at the cursor, editable by entering another name.
+const GUID_NAME: &str = "GUID name";
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let instance = bhiera.clone();
+    ui.on_inspect({
+        move |_revision, _selection_revision| {
+            let bhiera = instance.read().unwrap();
+            let bytes = bhiera
+                .data_provider()
+                .and_then(|provider| provider.get(bhiera.cursor(), INSPECTED_BYTES))
+                .unwrap_or_default();
+            let mut rows: Vec<InspectorRow> = ValueKind::all()
+                .iter()
+                .map(|kind| InspectorRow {
+                    name: kind.name().into(),
+                    value: kind.decode(&bytes).unwrap_or_default().into(),
+                })
+                .collect();
+            let guid = rows
+                .iter()
+                .position(|row| row.name == ValueKind::Guid.name())
+                .map_or(rows.len(), |index| index + 1);
+            rows.insert(
+                guid,
+                InspectorRow {
+                    name: GUID_NAME.into(),
+                    value: bhiera.guids().name_at(&bytes).unwrap_or_default().into(),
+                },
+            );
+            ModelRc::new(VecModel::from(rows))
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_edit_value({
+        move |name, text| {
+            let result = write_value(&mut instance.write().unwrap(), &name, &text);
+            match result {
+                Ok(_) => {
+                    update_status(&handle_weak, format!("Wrote {} {}", name, text));
+                    edited(&handle_weak, instance.clone());
+                }
+                Err(err) => update_status(&handle_weak, format!("Cannot write {}: {}", name, err)),
+            }
+        }
+    });
+}
+
+/// Replaces the value at the cursor, the cursor stays in place.
+fn write_value(bhiera: &mut Bhiera, name: &str, text: &str) -> Result<()> {
+    if name == GUID_NAME {
+        let guid = bhiera
+            .guids()
+            .guid(text.trim())
+            .ok_or_else(|| Error::msg("unknown GUID name"))?;
+        return write_bytes(bhiera, GUID_SIZE, guid.0.to_vec());
+    }
+    let kind = ValueKind::all()
+        .into_iter()
+        .find(|kind| kind.name() == name)
+        .ok_or_else(|| Error::msg("unknown type"))?;
+    let cursor = bhiera.cursor();
+    let size = bhiera
+        .data_provider()
+        .and_then(|provider| provider.get(cursor, INSPECTED_BYTES))
+        .and_then(|bytes| kind.size(&bytes))
+        .ok_or_else(|| Error::msg("not enough bytes at the cursor"))?;
+    write_bytes(bhiera, size, kind.encode_sized(text, size)?)
+}
+
+/// Replaces `size` bytes at the cursor, the cursor stays in place.
+fn write_bytes(bhiera: &mut Bhiera, size: usize, bytes: Vec<u8>) -> Result<()> {
+    let cursor = bhiera.cursor();
+    if bhiera
+        .data_provider()
+        .is_none_or(|provider| cursor + size > provider.len())
+    {
+        return Err(Error::msg("not enough bytes at the cursor"));
+    }
+    bhiera.edit(vec![Edit::new(cursor..cursor + size, bytes)])?;
+    bhiera.set_cursor(cursor);
+    Ok(())
+}
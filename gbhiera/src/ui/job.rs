@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use bhiera::{Bhiera, DataProvider, Model};
+
+/// Tracks the running instance of a background task so that starting a new
+/// one cancels the previous.
+#[derive(Clone, Default)]
+pub struct Job {
+    cancel: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+}
+
+impl Job {
+    pub fn start(&self) -> Arc<AtomicBool> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        if let Some(previous) = self.cancel.lock().unwrap().replace(cancel.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+        cancel
+    }
+
+    pub fn cancel(&self) {
+        if let Some(cancel) = self.cancel.lock().unwrap().take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The document a value was computed from, with the value.
+type Snapshot<T> = Option<(Arc<dyn DataProvider>, T)>;
+
+/// Result of a background task over a document, dropped once a different
+/// document is open or the document was edited.
+pub struct Computed<T> {
+    value: Arc<RwLock<Snapshot<T>>>,
+}
+
+impl<T> Clone for Computed<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T> Default for Computed<T> {
+    fn default() -> Self {
+        Self {
+            value: Arc::new(RwLock::new(None)),
+        }
+    }
+}
+
+impl<T> Computed<T> {
+    pub fn set(&self, provider: Arc<dyn DataProvider>, value: T) {
+        *self.value.write().unwrap() = Some((provider, value));
+    }
+
+    /// Calls `f` with the value if it was computed for the current document.
+    pub fn with<R, F>(&self, bhiera: &Bhiera, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let current = bhiera.snapshot()?;
+        match self.value.read().unwrap().as_ref() {
+            Some((provider, value)) if Arc::ptr_eq(provider, &current) => Some(f(value)),
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,24 @@
+mod annotations;
+mod bookmarks;
+mod checksums;
+mod clipboard;
+mod disassembly;
+mod edit;
+mod entropy;
+mod guids;
+mod inspector;
+mod job;
+mod overview;
+mod pixels;
 mod plotter;
+mod results;
+mod search;
+mod statistics;
+mod strides;
+mod strings;
+mod theme;
 mod ui;
 
+pub use clipboard::copy_to_clipboard;
 pub use plotter::Plotter;
 pub use ui::setup;
@@ -0,0 +1,72 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, RwLock};
+
+use bhiera::{overview, Bhiera, DataProvider, Model, OverviewRow};
+use slint::{ComponentHandle, Rgb8Pixel, SharedPixelBuffer};
+use tokio::runtime::Handle;
+
+use super::job::{Computed, Job};
+use crate::GbhieraUI;
+
+/// Document and row count of the running job.
+type Pending = Option<(Arc<dyn DataProvider>, usize)>;
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let job = Job::default();
+    let computed: Computed<Vec<OverviewRow>> = Computed::default();
+    // Rendering again while the job runs does not restart it.
+    let pending: Arc<Mutex<Pending>> = Arc::default();
+    let handle_weak = ui.as_weak();
+    ui.on_render_overview({
+        move |height, mode, _revision, _computed_revision| {
+            let bhiera = bhiera.read().unwrap();
+            let rows = height.max(0) as usize;
+            let mode = mode.parse().unwrap_or_default();
+            let theme = bhiera.theme();
+            let colors: Option<Vec<(u8, u8, u8)>> = computed
+                .with(&bhiera, |overview| {
+                    (overview.len() == rows)
+                        .then(|| overview.iter().map(|row| row.color(mode, &theme)).collect())
+                })
+                .flatten();
+            let colors = match colors {
+                Some(colors) if !colors.is_empty() => colors,
+                Some(_) => return slint::Image::default(),
+                None => {
+                    let provider = match bhiera.snapshot() {
+                        Some(provider) => provider,
+                        None => return slint::Image::default(),
+                    };
+                    let mut pending = pending.lock().unwrap();
+                    let running = pending.as_ref().is_some_and(|(running, running_rows)| {
+                        Arc::ptr_eq(running, &provider) && *running_rows == rows
+                    });
+                    if !running {
+                        pending.replace((provider.clone(), rows));
+                        let cancel = job.start();
+                        let handle_weak = handle_weak.clone();
+                        let computed = computed.clone();
+                        runtime.spawn_blocking(move || {
+                            let overview = overview(provider.as_ref(), rows, &cancel, |_| {});
+                            if cancel.load(Ordering::Relaxed) {
+                                return;
+                            }
+                            computed.set(provider, overview);
+                            handle_weak
+                                .upgrade_in_event_loop(|h| {
+                                    h.set_overview_revision(h.get_overview_revision() + 1)
+                                })
+                                .unwrap();
+                        });
+                    }
+                    return slint::Image::default();
+                }
+            };
+            let mut pixel_buffer = SharedPixelBuffer::<Rgb8Pixel>::new(1, colors.len() as u32);
+            for (pixel, (r, g, b)) in pixel_buffer.make_mut_slice().iter_mut().zip(colors) {
+                *pixel = Rgb8Pixel { r, g, b };
+            }
+            slint::Image::from_rgb8(pixel_buffer)
+        }
+    });
+}
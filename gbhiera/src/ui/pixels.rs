@@ -0,0 +1,124 @@
+use std::sync::{Arc, RwLock};
+
+use bhiera::{Bhiera, Model, PixelFormat, PixelLayout, Result};
+use image::RgbImage;
+use slint::{ComponentHandle, ModelRc, Rgb8Pixel, SharedPixelBuffer, SharedString, VecModel};
+
+use super::ui::{refresh, scroll_to_cursor, update_status};
+use crate::{GbhieraUI, PixelRows, PixelSettings};
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let formats: Vec<SharedString> = PixelFormat::ALL
+        .iter()
+        .map(|format| format.name().into())
+        .collect();
+    ui.set_pixel_formats(ModelRc::new(VecModel::from(formats)));
+    ui.on_pixels_in({
+        move |format, bytes| {
+            format
+                .parse::<PixelFormat>()
+                .map_or(0, |format| format.pixels_in(bytes.max(0) as usize) as i32)
+        }
+    });
+    let instance = bhiera.clone();
+    ui.on_pixel_rows({
+        move |settings, _revision| {
+            let bhiera = instance.read().unwrap();
+            let rows = pixel_layout(&settings).ok().and_then(|layout| {
+                let provider = bhiera.data_provider()?;
+                let (origin, cursor) = layout.rows_at(bhiera.cursor())?;
+                Some((layout.row_count(origin, provider.len()), cursor))
+            });
+            let (total, cursor) = rows.unwrap_or_default();
+            PixelRows {
+                total: total.min(i32::MAX as usize) as i32,
+                cursor: cursor.min(i32::MAX as usize) as i32,
+            }
+        }
+    });
+    let instance = bhiera.clone();
+    ui.on_render_pixels({
+        move |settings, first_row, rows, _revision| {
+            let layout = match pixel_layout(&settings) {
+                Ok(layout) => layout,
+                Err(_) => return slint::Image::default(),
+            };
+            let bhiera = instance.read().unwrap();
+            let provider = match bhiera.data_provider() {
+                Some(provider) => provider,
+                None => return slint::Image::default(),
+            };
+            let first = match layout
+                .rows_at(bhiera.cursor())
+                .and_then(|(origin, _)| layout.byte_at(origin, 0, first_row.max(0) as usize))
+            {
+                Some(first) => first,
+                None => return slint::Image::default(),
+            };
+            let (rgb, rows) = layout.render(provider, first, rows.max(1) as usize);
+            match RgbImage::from_raw(layout.width as u32, rows as u32, rgb) {
+                Some(image) if rows > 0 => {
+                    let pixel_buffer = SharedPixelBuffer::<Rgb8Pixel>::clone_from_slice(
+                        image.as_raw(),
+                        image.width(),
+                        image.height(),
+                    );
+                    slint::Image::from_rgb8(pixel_buffer)
+                }
+                _ => slint::Image::default(),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_pixel_clicked({
+        move |settings, x, y| {
+            let layout = match pixel_layout(&settings) {
+                Ok(layout) => layout,
+                Err(err) => {
+                    update_status(&handle_weak, format!("Invalid pixel settings: {}", err));
+                    return;
+                }
+            };
+            let mut bhiera = instance.write().unwrap();
+            let offset = match layout.rows_at(bhiera.cursor()).and_then(|(origin, _)| {
+                layout.byte_at(origin, x.max(0) as usize, y.max(0) as usize)
+            }) {
+                Some(offset) => offset,
+                None => {
+                    update_status(&handle_weak, "Pixel offset overflows");
+                    return;
+                }
+            };
+            bhiera.jump(offset..offset);
+            drop(bhiera);
+            scroll_to_cursor(&handle_weak, instance.clone());
+            refresh(&handle_weak);
+            handle_weak
+                .upgrade_in_event_loop(|h| h.set_current_tab(0))
+                .unwrap();
+        }
+    });
+}
+
+fn pixel_layout(settings: &PixelSettings) -> Result<PixelLayout> {
+    Ok(PixelLayout {
+        format: settings.format.parse()?,
+        width: settings.width.max(1) as usize,
+        stride: settings.stride.max(0) as usize,
+        offset: parse_number(&settings.offset)?.unwrap_or(0),
+        palette: parse_number(&settings.palette)?,
+    })
+}
+
+fn parse_number(text: &str) -> Result<Option<usize>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let value = match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16)?,
+        None => text.parse()?,
+    };
+    Ok(Some(value))
+}
@@ -2,7 +2,7 @@ use image::{ImageBuffer, Pixel, Rgb};
 use plotters::prelude::*;
 use slint::SharedPixelBuffer;
 
-use bhiera::{Bhiera, DataProvider, Element, Geometry, Model};
+use bhiera::{Bhiera, Element, Geometry, Model};
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -30,16 +30,12 @@ impl<'a> Plotter<'a> {
         }
     }
 
-    pub fn calculate_request_size<D: DataProvider + 'static>(&self, provider: &D) -> (u32, u32) {
-        (self.config.width(), self.config.height(provider.len()))
-    }
-
     pub fn plot(&self, bhiera: &Bhiera, view_start: i32, view_height: i32) -> slint::Image {
         let view = bhiera.get_view(view_start as u32, view_height as u32);
         match view {
             Some(view) => {
                 let mut pixel_buffer =
-                    SharedPixelBuffer::new(self.config.width(), view_height as u32);
+                    SharedPixelBuffer::new(bhiera.content_size().0, view_height as u32);
                 let size = (pixel_buffer.width(), pixel_buffer.height());
                 let mut backend = BitMapBackend::with_buffer(pixel_buffer.make_mut_bytes(), size);
 
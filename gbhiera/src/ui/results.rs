@@ -0,0 +1,174 @@
+use std::fs;
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{escape_bytes, Bhiera, DataProvider, Model, SearchHit};
+use slint::{ComponentHandle, Model as _, ModelRc, VecModel};
+
+use super::search::show_hit;
+use super::ui::update_status;
+use crate::{GbhieraUI, SearchResult};
+
+/// Rows shown in the results list, the hits themselves are not limited.
+const MAX_RESULTS: usize = 10_000;
+/// Bytes of a match or capture shown in the results list.
+const PREVIEW_BYTES: usize = 48;
+/// Bytes shown before and after a match.
+const CONTEXT_BYTES: usize = 8;
+
+struct ResultRow {
+    index: usize,
+    range: Range<usize>,
+    address: String,
+    label: String,
+    structure: String,
+    text: String,
+}
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_result_clicked({
+        move |index| {
+            let hit = instance.write().unwrap().select_hit(index as usize);
+            show_hit(&handle_weak, instance.clone(), hit);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    ui.on_sort_results({
+        move |column, descending| {
+            let handle = match handle_weak.upgrade() {
+                Some(handle) => handle,
+                None => return,
+            };
+            let mut results: Vec<SearchResult> = handle.get_search_results().iter().collect();
+            match column.as_str() {
+                "type" => results.sort_by(|a, b| a.label.cmp(&b.label)),
+                "structure" => results.sort_by(|a, b| a.structure.cmp(&b.structure)),
+                "match" => results.sort_by(|a, b| a.text.cmp(&b.text)),
+                _ => results.sort_by_key(|result| result.index),
+            }
+            if descending {
+                results.reverse();
+            }
+            handle.set_search_results(ModelRc::new(VecModel::from(results)));
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_export_results({
+        move || {
+            let path = match rfd::FileDialog::new()
+                .set_title("Export results")
+                .add_filter("CSV", &["csv"])
+                .save_file()
+            {
+                Some(path) => path,
+                None => return,
+            };
+            let csv = to_csv(&result_rows(&instance.read().unwrap(), usize::MAX));
+            match fs::write(&path, csv) {
+                Ok(_) => update_status(&handle_weak, format!("Exported {}", path.display())),
+                Err(err) => update_status(&handle_weak, format!("Export failed: {}", err)),
+            }
+        }
+    });
+}
+
+/// Rebuilds the results list from the hits of the model.
+pub(super) fn update_results(handle: &slint::Weak<GbhieraUI>, bhiera: &Bhiera) {
+    let rows = result_rows(bhiera, MAX_RESULTS);
+    handle
+        .upgrade_in_event_loop(move |h| {
+            let results: Vec<SearchResult> = rows
+                .into_iter()
+                .map(|row| SearchResult {
+                    index: row.index as i32,
+                    address: row.address.into(),
+                    label: row.label.into(),
+                    structure: row.structure.into(),
+                    text: row.text.into(),
+                })
+                .collect();
+            h.set_search_results(ModelRc::new(VecModel::from(results)));
+        })
+        .unwrap();
+}
+
+pub(super) fn show_results_tab(handle: &slint::Weak<GbhieraUI>) {
+    handle
+        .upgrade_in_event_loop(move |h| {
+            h.set_side_tab(1);
+        })
+        .unwrap();
+}
+
+fn result_rows(bhiera: &Bhiera, limit: usize) -> Vec<ResultRow> {
+    let provider = match bhiera.data_provider() {
+        Some(provider) => provider,
+        None => return Vec::new(),
+    };
+    let address_mode = bhiera.address_mode();
+    bhiera
+        .search_hits()
+        .iter()
+        .take(limit)
+        .enumerate()
+        .map(|(index, hit)| ResultRow {
+            index,
+            range: hit.range.clone(),
+            address: address_mode.format(hit.range.start),
+            label: hit.label.clone(),
+            structure: bhiera
+                .annotations_at(hit.range.start)
+                .first()
+                .map(|annotation| annotation.label.clone())
+                .unwrap_or_default(),
+            text: result_text(provider, hit),
+        })
+        .collect()
+}
+
+/// The match in its context followed by the capture groups as `$1="..."`.
+fn result_text(provider: &dyn DataProvider, hit: &SearchHit) -> String {
+    let preview = |range: Range<usize>, max: usize| match provider.get(range.start, range.len()) {
+        Some(bytes) => escape_bytes(&bytes, max),
+        None => String::new(),
+    };
+    let before = hit.range.start.saturating_sub(CONTEXT_BYTES)..hit.range.start;
+    let after = hit.range.end..hit.range.end + CONTEXT_BYTES;
+    let mut text = format!(
+        "{}[{}]{}",
+        preview(before, CONTEXT_BYTES),
+        preview(hit.range.clone(), PREVIEW_BYTES),
+        preview(after, CONTEXT_BYTES)
+    );
+    for (index, capture) in hit.captures.iter().enumerate() {
+        match capture {
+            Some(range) => text.push_str(&format!(
+                " ${}=\"{}\"",
+                index + 1,
+                preview(range.clone(), PREVIEW_BYTES)
+            )),
+            None => text.push_str(&format!(" ${}=-", index + 1)),
+        }
+    }
+    text
+}
+
+fn to_csv(rows: &[ResultRow]) -> String {
+    let quote = |field: &str| format!("\"{}\"", field.replace('"', "\"\""));
+    let mut csv = String::from("offset,end,address,type,structure,match\n");
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            row.range.start,
+            row.range.end,
+            quote(&row.address),
+            quote(&row.label),
+            quote(&row.structure),
+            quote(&row.text)
+        ));
+    }
+    csv
+}
@@ -0,0 +1,281 @@
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{
+    find_all, replacement_bytes, Bhiera, BytePattern, Edit, Model, Pattern, RegexPattern, Result,
+    SearchHit, SignatureSet, TextPattern,
+};
+use slint::ComponentHandle;
+use tokio::runtime::Handle;
+
+use super::edit::edited;
+use super::job::Job;
+use super::results::{show_results_tab, update_results};
+use super::ui::{refresh, scroll_to_cursor, update_status};
+use crate::GbhieraUI;
+
+/// Bytes a regex match may extend into the next chunk.
+const REGEX_OVERLAP: usize = 4096;
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let job = Job::default();
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let search_job = job.clone();
+    let search_runtime = runtime.clone();
+    ui.on_search_hex({
+        move |text| match text.parse::<BytePattern>() {
+            Ok(pattern) => start_search(
+                &handle_weak,
+                instance.clone(),
+                &search_runtime,
+                &search_job,
+                Box::new(pattern),
+            ),
+            Err(err) => update_status(&handle_weak, format!("Invalid pattern: {}", err)),
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let search_job = job.clone();
+    let search_runtime = runtime.clone();
+    ui.on_search_text({
+        move |text, case_sensitive| match TextPattern::new(&text, !case_sensitive) {
+            Ok(pattern) => start_search(
+                &handle_weak,
+                instance.clone(),
+                &search_runtime,
+                &search_job,
+                Box::new(pattern),
+            ),
+            Err(err) => update_status(&handle_weak, format!("Invalid text: {}", err)),
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let search_job = job.clone();
+    let search_runtime = runtime.clone();
+    ui.on_search_regex({
+        move |text| match RegexPattern::new(&text, REGEX_OVERLAP) {
+            Ok(pattern) => start_search(
+                &handle_weak,
+                instance.clone(),
+                &search_runtime,
+                &search_job,
+                Box::new(pattern),
+            ),
+            Err(err) => update_status(&handle_weak, format!("Invalid regex: {}", err)),
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let search_job = job.clone();
+    let search_runtime = runtime.clone();
+    ui.on_search_guid({
+        move |text| {
+            let guids = instance.read().unwrap().guids().matching(&text);
+            if guids.is_empty() {
+                update_status(&handle_weak, format!("No GUID named like {}", text));
+                return;
+            }
+            start_search(
+                &handle_weak,
+                instance.clone(),
+                &search_runtime,
+                &search_job,
+                Box::new(guids),
+            );
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let search_job = job.clone();
+    ui.on_scan_signatures({
+        move || {
+            let mut signatures = SignatureSet::builtin();
+            if let Some(path) = signatures_path().filter(|path| path.exists()) {
+                match SignatureSet::load(&path) {
+                    Ok(mut user_signatures) => signatures.append(&mut user_signatures),
+                    Err(err) => {
+                        update_status(
+                            &handle_weak,
+                            format!("Loading {} failed: {}", path.display(), err),
+                        );
+                        return;
+                    }
+                }
+            }
+            start_search(
+                &handle_weak,
+                instance.clone(),
+                &runtime,
+                &search_job,
+                Box::new(SignatureSet::new(signatures)),
+            );
+        }
+    });
+    ui.on_cancel_search({
+        move || {
+            job.cancel();
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_search_next({
+        move || {
+            let hit = instance.write().unwrap().next_hit();
+            show_hit(&handle_weak, instance.clone(), hit);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_search_previous({
+        move || {
+            let hit = instance.write().unwrap().previous_hit();
+            show_hit(&handle_weak, instance.clone(), hit);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_replace({
+        move |text| {
+            // The first click selects a hit, the next one replaces it.
+            let result = replace_selected(&mut instance.write().unwrap(), &text);
+            match result {
+                Ok(true) => {
+                    let hit = instance.write().unwrap().next_hit();
+                    edited(&handle_weak, instance.clone());
+                    if hit.is_some() {
+                        show_hit(&handle_weak, instance.clone(), hit);
+                    }
+                }
+                Ok(false) => {
+                    let hit = instance.write().unwrap().next_hit();
+                    show_hit(&handle_weak, instance.clone(), hit);
+                }
+                Err(err) => update_status(&handle_weak, format!("Replace failed: {}", err)),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_replace_all({
+        move |text| {
+            let result = replace_all(&mut instance.write().unwrap(), &text);
+            match result {
+                Ok(0) => update_status(&handle_weak, "No search hits"),
+                Ok(count) => {
+                    update_status(&handle_weak, format!("Replaced {} occurrences", count));
+                    edited(&handle_weak, instance.clone());
+                    scroll_to_cursor(&handle_weak, instance.clone());
+                }
+                Err(err) => update_status(&handle_weak, format!("Replace failed: {}", err)),
+            }
+        }
+    });
+}
+
+fn signatures_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("gbhiera").join("signatures.toml"))
+}
+
+/// Replaces the hit under the selection, `false` if no hit is selected.
+fn replace_selected(bhiera: &mut Bhiera, text: &str) -> Result<bool> {
+    let hit = match bhiera.selected_hit() {
+        Some(index) => bhiera.search_hits()[index].clone(),
+        None => return Ok(false),
+    };
+    let bytes = replacement_bytes(&hit, text)?;
+    bhiera.edit(vec![Edit::new(hit.range, bytes)])?;
+    Ok(true)
+}
+
+/// Replaces all hits as one undoable edit. Hits overlapping an earlier
+/// one are skipped.
+fn replace_all(bhiera: &mut Bhiera, text: &str) -> Result<usize> {
+    let mut edits: Vec<Edit> = Vec::new();
+    for hit in bhiera.search_hits() {
+        if edits
+            .last()
+            .is_some_and(|edit| hit.range.start < edit.range.end)
+        {
+            continue;
+        }
+        edits.push(Edit::new(hit.range.clone(), replacement_bytes(hit, text)?));
+    }
+    let count = edits.len();
+    if count > 0 {
+        bhiera.edit(edits)?;
+    }
+    Ok(count)
+}
+
+fn start_search(
+    handle: &slint::Weak<GbhieraUI>,
+    bhiera: Arc<RwLock<Bhiera>>,
+    runtime: &Handle,
+    job: &Job,
+    pattern: Box<dyn Pattern>,
+) {
+    let provider = match bhiera.read().unwrap().snapshot() {
+        Some(provider) => provider,
+        None => return,
+    };
+    let cancel = job.start();
+    let handle_weak = handle.clone();
+    runtime.spawn_blocking(move || {
+        let total = provider.len();
+        let hits = find_all(provider.as_ref(), pattern.as_ref(), &cancel, |done| {
+            let percent = done * 100 / total.max(1);
+            update_status(&handle_weak, format!("Searching... {}%", percent));
+        });
+        if cancel.load(Ordering::Relaxed) {
+            update_status(&handle_weak, "Search cancelled");
+            return;
+        }
+        let count = hits.len();
+        let mut instance = bhiera.write().unwrap();
+        // Hits found in a document that was since edited or replaced are stale.
+        let current = instance
+            .snapshot()
+            .is_some_and(|current| Arc::ptr_eq(&current, &provider));
+        if !current {
+            update_status(&handle_weak, "Search cancelled: the document changed");
+            return;
+        }
+        instance.set_search_hits(hits);
+        let hit = instance.next_hit();
+        drop(instance);
+        update_results(&handle_weak, &bhiera.read().unwrap());
+        match hit {
+            Some(_) => {
+                update_status(&handle_weak, format!("{} hits", count));
+                show_results_tab(&handle_weak);
+                scroll_to_cursor(&handle_weak, bhiera.clone());
+            }
+            None => update_status(&handle_weak, "Not found"),
+        }
+        refresh(&handle_weak);
+    });
+}
+
+pub(super) fn show_hit(
+    handle: &slint::Weak<GbhieraUI>,
+    bhiera: Arc<RwLock<Bhiera>>,
+    hit: Option<SearchHit>,
+) {
+    match hit {
+        Some(hit) => {
+            let address = bhiera
+                .read()
+                .unwrap()
+                .address_mode()
+                .format(hit.range.start);
+            update_status(handle, format!("Hit at {} ({})", address, hit.label));
+            scroll_to_cursor(handle, bhiera);
+            refresh(handle);
+        }
+        None => update_status(handle, "No search hits"),
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{histogram, Bhiera, ByteClass, Histogram, Model, Theme};
+use plotters::prelude::*;
+use slint::{ComponentHandle, Rgb8Pixel, SharedPixelBuffer};
+use tokio::runtime::Handle;
+
+use super::job::{Computed, Job};
+use super::ui::update_status;
+use crate::GbhieraUI;
+
+/// Bytes listed as most common in the summary.
+const MOST_COMMON: usize = 8;
+
+/// Histogram of a range of the document, with a description of the range.
+struct Statistics {
+    histogram: Histogram,
+    range: String,
+}
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let job = Job::default();
+    let computed: Computed<Statistics> = Computed::default();
+    let instance = bhiera.clone();
+    let current = computed.clone();
+    ui.on_render_histogram({
+        move |width, height, _revision, _computed_revision| {
+            let bhiera = instance.read().unwrap();
+            let size = (width.max(0) as u32, height.max(0) as u32);
+            current
+                .with(&bhiera, |statistics| {
+                    render_histogram(&statistics.histogram, &bhiera.theme(), size)
+                })
+                .unwrap_or_default()
+        }
+    });
+    let instance = bhiera.clone();
+    let current = computed.clone();
+    ui.on_statistics_range({
+        move |_revision, _computed_revision| {
+            current
+                .with(&instance.read().unwrap(), |statistics| {
+                    statistics.range.clone()
+                })
+                .unwrap_or_default()
+                .into()
+        }
+    });
+    let instance = bhiera.clone();
+    let current = computed.clone();
+    ui.on_statistics_summary({
+        move |_revision, _computed_revision| {
+            current
+                .with(&instance.read().unwrap(), |statistics| {
+                    summary(&statistics.histogram)
+                })
+                .unwrap_or_default()
+                .into()
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_compute_statistics({
+        move || {
+            let bhiera = instance.read().unwrap();
+            let provider = match bhiera.snapshot() {
+                Some(provider) => provider,
+                None => return,
+            };
+            let range = bhiera.selection_or_document();
+            let address_mode = bhiera.address_mode();
+            let description = format!(
+                "{}..{} ({} bytes)",
+                address_mode.format(range.start),
+                address_mode.format(range.end),
+                range.len()
+            );
+            drop(bhiera);
+            let cancel = job.start();
+            let handle_weak = handle_weak.clone();
+            let computed = computed.clone();
+            runtime.spawn_blocking(move || {
+                let total = range.len();
+                let histogram = histogram(provider.as_ref(), range, &cancel, |done| {
+                    let percent = done * 100 / total.max(1);
+                    update_status(
+                        &handle_weak,
+                        format!("Computing statistics... {}%", percent),
+                    );
+                });
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                update_status(
+                    &handle_weak,
+                    format!("Computed statistics of {}", description),
+                );
+                computed.set(
+                    provider,
+                    Statistics {
+                        histogram,
+                        range: description,
+                    },
+                );
+                handle_weak
+                    .upgrade_in_event_loop(move |h| {
+                        h.set_statistics_revision(h.get_statistics_revision() + 1);
+                    })
+                    .unwrap();
+            });
+        }
+    });
+}
+
+fn summary(histogram: &Histogram) -> String {
+    let total = histogram.total().max(1) as f64;
+    let most_common: Vec<String> = histogram
+        .most_common(MOST_COMMON)
+        .iter()
+        .map(|(byte, count)| format!("{:02x} ({:.1}%)", byte, *count as f64 * 100.0 / total))
+        .collect();
+    format!(
+        "Bytes: {}\n\
+         Entropy: {:.4} bits per byte\n\
+         Chi-square: {:.1} (255 degrees of freedom)\n\
+         Mean: {:.4}\n\
+         Printable: {:.1}%\n\
+         Most common: {}",
+        histogram.total(),
+        histogram.entropy(),
+        histogram.chi_square(),
+        histogram.mean(),
+        histogram.printable_ratio() * 100.0,
+        most_common.join(", ")
+    )
+}
+
+fn render_histogram(histogram: &Histogram, theme: &Theme, size: (u32, u32)) -> slint::Image {
+    if size.0 == 0 || size.1 == 0 {
+        return slint::Image::default();
+    }
+    let mut pixel_buffer = SharedPixelBuffer::<Rgb8Pixel>::new(size.0, size.1);
+    let backend = BitMapBackend::with_buffer(pixel_buffer.make_mut_bytes(), size);
+    match draw_histogram(backend.into_drawing_area(), histogram, theme) {
+        Ok(_) => slint::Image::from_rgb8(pixel_buffer),
+        Err(_) => slint::Image::default(),
+    }
+}
+
+/// Draws one bar per byte value, colored by its byte class.
+fn draw_histogram<DB: DrawingBackend>(
+    area: DrawingArea<DB, plotters::coord::Shift>,
+    histogram: &Histogram,
+    theme: &Theme,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let color = |(r, g, b): (u8, u8, u8)| RGBColor(r, g, b);
+    let foreground = color(theme.foreground);
+    area.fill(&color(theme.background))?;
+    let max = histogram.counts().iter().copied().max().unwrap_or(0).max(1);
+    let mut chart = ChartBuilder::on(&area)
+        .margin(4)
+        .x_label_area_size(16)
+        .y_label_area_size(48)
+        .build_cartesian_2d(0u32..256u32, 0u64..max)?;
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .axis_style(foreground)
+        .label_style(("sans-serif", 10).into_font().color(&foreground))
+        .x_labels(9)
+        .x_label_formatter(&|byte| format!("{:02x}", byte))
+        .draw()?;
+    chart.draw_series(histogram.counts().iter().enumerate().map(|(byte, count)| {
+        let fill = color(theme.class_color(ByteClass::of(byte as u8)));
+        Rectangle::new([(byte as u32, 0), (byte as u32 + 1, *count)], fill.filled())
+    }))?;
+    area.present()
+}
@@ -0,0 +1,71 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{find_strides, Bhiera, Model};
+use slint::{ComponentHandle, ModelRc, VecModel};
+use tokio::runtime::Handle;
+
+use super::job::Job;
+use super::ui::update_status;
+use crate::{GbhieraUI, StrideItem};
+
+/// Largest record size looked for.
+const MAX_STRIDE: usize = 512;
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let job = Job::default();
+    let handle_weak = ui.as_weak();
+    let instance = bhiera;
+    ui.on_detect_strides({
+        move || {
+            let bhiera = instance.read().unwrap();
+            let provider = match bhiera.snapshot() {
+                Some(provider) => provider,
+                None => return,
+            };
+            let range = bhiera.selection_or_document();
+            let address_mode = bhiera.address_mode();
+            let description = format!(
+                "{}..{} ({} bytes)",
+                address_mode.format(range.start),
+                address_mode.format(range.end),
+                range.len()
+            );
+            drop(bhiera);
+            let cancel = job.start();
+            let handle_weak = handle_weak.clone();
+            runtime.spawn_blocking(move || {
+                // Small ranges are searched for strides up to half their size.
+                let total = std::cmp::min(MAX_STRIDE, range.len() / 2).max(1);
+                let strides = find_strides(provider.as_ref(), range, MAX_STRIDE, &cancel, |done| {
+                    let percent = done * 100 / total;
+                    update_status(&handle_weak, format!("Detecting strides... {}%", percent));
+                });
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                update_status(
+                    &handle_weak,
+                    match strides.first() {
+                        Some(best) => format!("Likely stride {} in {}", best.stride, description),
+                        None => format!("No stride found in {}", description),
+                    },
+                );
+                handle_weak
+                    .upgrade_in_event_loop(move |h| {
+                        let items: Vec<StrideItem> = strides
+                            .iter()
+                            .map(|stride| StrideItem {
+                                stride: stride.stride as i32,
+                                label: format!("{} (0x{:x})", stride.stride, stride.stride).into(),
+                                score: format!("+{:.1}% matches", stride.score * 100.0).into(),
+                            })
+                            .collect();
+                        h.set_strides(ModelRc::new(VecModel::from(items)));
+                        h.set_strides_range(description.into());
+                    })
+                    .unwrap();
+            });
+        }
+    });
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{find_strings, Bhiera, FoundString, Model};
+use slint::{ComponentHandle, ModelRc, VecModel};
+use tokio::runtime::Handle;
+
+use super::job::{Computed, Job};
+use super::ui::{refresh, scroll_to_cursor, update_status};
+use crate::{GbhieraUI, StringItem};
+
+/// Rows shown in the strings list, the extracted strings are not limited.
+const MAX_STRINGS: usize = 100_000;
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
+    let job = Job::default();
+    let extracted: Computed<Vec<FoundString>> = Computed::default();
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let strings = extracted.clone();
+    ui.on_extract_strings({
+        move |min_length| {
+            let bhiera = instance.read().unwrap();
+            let provider = match bhiera.snapshot() {
+                Some(provider) => provider,
+                None => return,
+            };
+            let range = bhiera.selection_or_document();
+            drop(bhiera);
+            let cancel = job.start();
+            let handle_weak = handle_weak.clone();
+            let instance = instance.clone();
+            let strings = strings.clone();
+            runtime.spawn_blocking(move || {
+                let total = range.len();
+                let found = find_strings(
+                    provider.as_ref(),
+                    range,
+                    min_length as usize,
+                    &cancel,
+                    |done| {
+                        let percent = done * 100 / total.max(1);
+                        update_status(&handle_weak, format!("Extracting strings... {}%", percent));
+                    },
+                );
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                update_status(&handle_weak, format!("{} strings", found.len()));
+                strings.set(provider, found);
+                handle_weak
+                    .upgrade_in_event_loop(move |h| {
+                        let filter = h.get_strings_filter();
+                        show_strings(&h, &instance.read().unwrap(), &strings, &filter);
+                    })
+                    .unwrap();
+            });
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    let strings = extracted.clone();
+    ui.on_filter_strings({
+        move |filter| {
+            if let Some(handle) = handle_weak.upgrade() {
+                show_strings(&handle, &instance.read().unwrap(), &strings, &filter);
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_string_clicked({
+        move |index| {
+            let range = extracted.with(&instance.read().unwrap(), |strings| {
+                strings
+                    .get(index as usize)
+                    .map(|string| string.range.clone())
+            });
+            let range = match range.flatten() {
+                Some(range) => range,
+                None => return,
+            };
+            instance.write().unwrap().jump(range);
+            scroll_to_cursor(&handle_weak, instance.clone());
+            refresh(&handle_weak);
+        }
+    });
+}
+
+fn show_strings(
+    handle: &GbhieraUI,
+    bhiera: &Bhiera,
+    extracted: &Computed<Vec<FoundString>>,
+    filter: &str,
+) {
+    let address_mode = bhiera.address_mode();
+    let filter = filter.to_lowercase();
+    let items: Vec<StringItem> = extracted
+        .with(bhiera, |strings| {
+            strings
+                .iter()
+                .enumerate()
+                .filter(|(_, string)| string.text.to_lowercase().contains(&filter))
+                .take(MAX_STRINGS)
+                .map(|(index, string)| StringItem {
+                    index: index as i32,
+                    address: address_mode.format(string.range.start).into(),
+                    encoding: string.encoding.name().into(),
+                    length: string.len() as i32,
+                    text: string.text.clone().into(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    handle.set_strings(ModelRc::new(VecModel::from(items)));
+}
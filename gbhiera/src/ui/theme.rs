@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use bhiera::{Bhiera, Model, Theme};
+use slint::{Color, ComponentHandle, ModelRc, SharedString, VecModel};
+
+use super::ui::{refresh, update_status};
+use crate::{AppTheme, GbhieraUI};
+
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+    let handle_weak = ui.as_weak();
+    let mut themes = Theme::builtin();
+    if let Some(path) = config_path().filter(|path| path.exists()) {
+        match Theme::load(&path) {
+            // A user theme named like a built-in one replaces it.
+            Ok(user_themes) => {
+                for (name, theme) in user_themes {
+                    match themes.iter_mut().find(|(n, _)| *n == name) {
+                        Some(existing) => existing.1 = theme,
+                        None => themes.push((name, theme)),
+                    }
+                }
+            }
+            Err(err) => update_status(
+                &handle_weak,
+                format!("Loading {} failed: {}", path.display(), err),
+            ),
+        }
+    }
+    let names: Vec<SharedString> = themes.iter().map(|(name, _)| name.into()).collect();
+    ui.set_themes(ModelRc::new(VecModel::from(names)));
+    bhiera.write().unwrap().set_theme(themes[0].1);
+    apply(ui, &themes[0].1);
+
+    let instance = bhiera.clone();
+    ui.on_theme_changed({
+        move |name| {
+            if let Some((_, theme)) = themes.iter().find(|(n, _)| n == name.as_str()) {
+                instance.write().unwrap().set_theme(*theme);
+                if let Some(ui) = handle_weak.upgrade() {
+                    apply(&ui, theme);
+                }
+                refresh(&handle_weak);
+            }
+        }
+    });
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("gbhiera").join("themes.toml"))
+}
+
+fn apply(ui: &GbhieraUI, theme: &Theme) {
+    let color = |(r, g, b): (u8, u8, u8)| Color::from_rgb_u8(r, g, b);
+    let globals = ui.global::<AppTheme>();
+    globals.set_background(color(theme.background));
+    globals.set_foreground(color(theme.foreground));
+    globals.set_offset_background(color(theme.offset_background));
+    globals.set_offset_text(color(theme.offset_text));
+    globals.set_selection(color(theme.selection));
+}
@@ -1,40 +1,73 @@
 use std::sync::{Arc, RwLock};
 
-use bhiera::{Bhiera, FileDataProvider, Model};
+use bhiera::{
+    AddressMode, Bhiera, ColorScheme, Error, FileDataProvider, Model, Movement, Result, ValueKind,
+};
 use rfd;
-use slint::ComponentHandle;
+use slint::platform::Key;
+use slint::{ComponentHandle, ModelRc, SharedString, VecModel};
+use tokio::runtime::Handle;
 
-use super::Plotter;
+use super::annotations::update_annotations;
+use super::bookmarks::{hash_document, update_bookmarks};
+use super::job::Job;
+use super::{
+    annotations, bookmarks, checksums, copy_to_clipboard, disassembly, edit, entropy, guids,
+    inspector, overview, pixels, results, search, statistics, strides, strings, theme, Plotter,
+};
 use crate::GbhieraUI;
 
-pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
+pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>, runtime: Handle) {
     let orig_plotter = Plotter::with_font("Courier New", 18.0);
     bhiera.write().unwrap().set_geometry(&orig_plotter.config);
     let handle_weak = ui.as_weak();
     let instance = bhiera.clone();
-    let plotter = orig_plotter.clone();
+    let hash_job = Job::default();
+    let hash_runtime = runtime.clone();
     ui.on_show_open_dialog({
         move || {
             let data_provider = load_data_provider(handle_weak.clone());
             if let Some(binary_data) = data_provider {
-                let (hexview_width, hexview_height) = plotter.calculate_request_size(&binary_data);
                 handle_weak
                     .upgrade_in_event_loop(move |h| {
-                        h.set_hexview_width(hexview_width as f32);
-                        h.set_hexview_height(hexview_height as f32);
+                        h.set_search_results(ModelRc::default());
+                        h.set_checksums(ModelRc::default());
+                        h.set_checksum_range("".into());
+                        h.set_strings(ModelRc::default());
+                        h.set_strides(ModelRc::default());
+                        h.set_strides_range("".into());
+                        h.set_entropy_edges(ModelRc::default());
                     })
                     .unwrap();
 
-                instance.write().unwrap().set_data_provider(binary_data);
+                let path = binary_data.to_path().to_path_buf();
+                let mut bhiera = instance.write().unwrap();
+                bhiera.set_data_provider(binary_data);
+                resize(&handle_weak, instance.clone());
+                if let Err(err) = bhiera.open_sidecar(&path) {
+                    update_status(&handle_weak, format!("Cannot load bookmarks: {}", err));
+                }
+                update_bookmarks(&handle_weak, &bhiera);
+                update_annotations(&handle_weak, &bhiera);
+                refresh(&handle_weak);
+                drop(bhiera);
+                hash_document(
+                    &handle_weak,
+                    instance.clone(),
+                    &hash_runtime,
+                    &hash_job,
+                    false,
+                );
             }
         }
     });
     let instance = bhiera.clone();
     let plotter = orig_plotter.clone();
     ui.on_render_plot({
-        move |view_start, view_height, _begin, _end| {
+        move |view_start, view_height, _begin, _end, _revision| {
             let mut bhiera = instance.write().unwrap();
             bhiera.set_view_y(view_start as u32);
+            bhiera.set_view_height(view_height as u32);
             drop(bhiera);
             let bhiera = instance.read().unwrap();
             plotter.plot(&bhiera, view_start, view_height)
@@ -52,6 +85,203 @@ pub fn setup(ui: &GbhieraUI, bhiera: Arc<RwLock<Bhiera>>) {
             instance.write().unwrap().set_selection_end(x, y);
         }
     });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_move_cursor({
+        move |key, shift, control| match movement(&key, control) {
+            Some(movement) => {
+                instance.write().unwrap().move_cursor(movement, shift);
+                scroll_to_cursor(&handle_weak, instance.clone());
+                refresh(&handle_weak);
+                true
+            }
+            None => false,
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_navigate_back({
+        move || {
+            let moved = instance.write().unwrap().navigate_back();
+            match moved {
+                true => {
+                    scroll_to_cursor(&handle_weak, instance.clone());
+                    refresh(&handle_weak);
+                }
+                false => update_status(&handle_weak, "No previous location"),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_navigate_forward({
+        move || {
+            let moved = instance.write().unwrap().navigate_forward();
+            match moved {
+                true => {
+                    scroll_to_cursor(&handle_weak, instance.clone());
+                    refresh(&handle_weak);
+                }
+                false => update_status(&handle_weak, "No next location"),
+            }
+        }
+    });
+    let schemes: Vec<SharedString> = ColorScheme::ALL
+        .iter()
+        .map(|scheme| scheme.name().into())
+        .collect();
+    ui.set_color_schemes(ModelRc::new(VecModel::from(schemes)));
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_color_scheme_changed({
+        move |name| {
+            if let Ok(scheme) = name.parse() {
+                instance.write().unwrap().set_color_scheme(scheme);
+                refresh(&handle_weak);
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_address_mode_changed({
+        move |mode, value| {
+            let mut bhiera = instance.write().unwrap();
+            match address_mode(&mode, &value, bhiera.cursor()) {
+                Ok(address_mode) => {
+                    bhiera.set_address_mode(address_mode);
+                    update_bookmarks(&handle_weak, &bhiera);
+                    update_annotations(&handle_weak, &bhiera);
+                    refresh(&handle_weak);
+                }
+                Err(err) => update_status(&handle_weak, format!("Invalid {} value: {}", mode, err)),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_row_width_changed({
+        move |width| {
+            instance
+                .write()
+                .unwrap()
+                .set_bytes_per_line(width.max(1) as usize);
+            resize(&handle_weak, instance.clone());
+            scroll_to_cursor(&handle_weak, instance.clone());
+            refresh(&handle_weak);
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_copy_address({
+        move || {
+            let address = instance.read().unwrap().cursor_address();
+            match copy_to_clipboard(address.as_str()) {
+                Ok(_) => update_status(&handle_weak, format!("Copied address {}", address)),
+                Err(err) => update_status(&handle_weak, format!("Copy failed: {}", err)),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_follow_pointer({
+        move |kind, mode, value| {
+            let result = follow_pointer(&mut instance.write().unwrap(), &kind, &mode, &value);
+            match result {
+                Ok(_) => {
+                    let address = instance.read().unwrap().cursor_address();
+                    update_status(&handle_weak, format!("Followed {} to {}", kind, address));
+                    scroll_to_cursor(&handle_weak, instance.clone());
+                    refresh(&handle_weak);
+                }
+                Err(err) => update_status(&handle_weak, format!("Cannot follow {}: {}", kind, err)),
+            }
+        }
+    });
+    let handle_weak = ui.as_weak();
+    let instance = bhiera.clone();
+    ui.on_goto({
+        move |expression| {
+            let result = instance.write().unwrap().goto(&expression);
+            match result {
+                Ok(_) => {
+                    let address = instance.read().unwrap().cursor_address();
+                    update_status(&handle_weak, format!("Went to {}", address));
+                    scroll_to_cursor(&handle_weak, instance.clone());
+                    refresh(&handle_weak);
+                }
+                Err(err) => update_status(&handle_weak, format!("Go to failed: {}", err)),
+            }
+        }
+    });
+    annotations::setup(ui, bhiera.clone());
+    bookmarks::setup(ui, bhiera.clone());
+    checksums::setup(ui, bhiera.clone(), runtime.clone());
+    disassembly::setup(ui, bhiera.clone());
+    edit::setup(ui, bhiera.clone(), runtime.clone());
+    entropy::setup(ui, bhiera.clone(), runtime.clone());
+    guids::setup(ui, bhiera.clone());
+    inspector::setup(ui, bhiera.clone());
+    overview::setup(ui, bhiera.clone(), runtime.clone());
+    pixels::setup(ui, bhiera.clone());
+    results::setup(ui, bhiera.clone());
+    search::setup(ui, bhiera.clone(), runtime.clone());
+    statistics::setup(ui, bhiera.clone(), runtime.clone());
+    strides::setup(ui, bhiera.clone(), runtime.clone());
+    strings::setup(ui, bhiera.clone(), runtime);
+    theme::setup(ui, bhiera);
+}
+
+fn address_mode(mode: &str, value: &str, cursor: usize) -> Result<AddressMode> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("0x")
+        .or(value.strip_prefix("0X"))
+        .unwrap_or(value);
+    match mode {
+        "mapped" => Ok(AddressMode::Mapped(u64::from_str_radix(value, 16)?)),
+        "decimal" => Ok(AddressMode::Decimal),
+        "relative" if value.is_empty() => Ok(AddressMode::Relative(cursor)),
+        "relative" => Ok(AddressMode::Relative(usize::from_str_radix(value, 16)?)),
+        _ => Ok(AddressMode::FileOffset),
+    }
+}
+
+fn follow_pointer(bhiera: &mut Bhiera, kind: &str, mode: &str, value: &str) -> Result<usize> {
+    let (size, signed, endian) = ValueKind::all()
+        .into_iter()
+        .find_map(|candidate| match candidate {
+            ValueKind::Int {
+                size,
+                signed,
+                endian,
+            } if candidate.name() == kind => Some((size, signed, endian)),
+            _ => None,
+        })
+        .ok_or_else(|| Error::msg("unknown pointer type"))?;
+    let mode = address_mode(mode, value, bhiera.cursor())?;
+    bhiera.follow_pointer(size, signed, endian, mode)
+}
+
+fn movement(key: &str, control: bool) -> Option<Movement> {
+    let key = key.chars().next()?;
+    let (_, movement) = [
+        (Key::LeftArrow, Movement::Left),
+        (Key::RightArrow, Movement::Right),
+        (Key::UpArrow, Movement::Up),
+        (Key::DownArrow, Movement::Down),
+        (Key::PageUp, Movement::PageUp),
+        (Key::PageDown, Movement::PageDown),
+        (Key::Home, Movement::LineStart),
+        (Key::End, Movement::LineEnd),
+    ]
+    .into_iter()
+    .find(|(candidate, _)| char::from(*candidate) == key)?;
+    let movement = match movement {
+        Movement::LineStart if control => Movement::DocumentStart,
+        Movement::LineEnd if control => Movement::DocumentEnd,
+        movement => movement,
+    };
+    Some(movement)
 }
 
 fn load_data_provider(handle: slint::Weak<GbhieraUI>) -> Option<FileDataProvider> {
@@ -85,7 +315,36 @@ fn load_data_provider(handle: slint::Weak<GbhieraUI>) -> Option<FileDataProvider
     Some(binary_data)
 }
 
-fn update_status<S>(handle: &slint::Weak<GbhieraUI>, msg: S)
+pub(super) fn refresh(handle: &slint::Weak<GbhieraUI>) {
+    handle
+        .upgrade_in_event_loop(move |h| {
+            h.set_hexview_revision(h.get_hexview_revision() + 1);
+        })
+        .unwrap();
+}
+
+pub(super) fn scroll_to_cursor(handle: &slint::Weak<GbhieraUI>, bhiera: Arc<RwLock<Bhiera>>) {
+    handle
+        .upgrade_in_event_loop(move |h| {
+            let view_height = h.get_hexview_visible_height() as u32;
+            let view_start = bhiera.read().unwrap().scroll_to_cursor(view_height);
+            h.set_hexview_viewport_y(-(view_start as f32));
+        })
+        .unwrap();
+}
+
+/// Resizes the hex view after the document length or row width changed.
+pub(super) fn resize(handle: &slint::Weak<GbhieraUI>, bhiera: Arc<RwLock<Bhiera>>) {
+    handle
+        .upgrade_in_event_loop(move |h| {
+            let (width, height) = bhiera.read().unwrap().content_size();
+            h.set_hexview_width(width as f32);
+            h.set_hexview_height(height as f32);
+        })
+        .unwrap();
+}
+
+pub(super) fn update_status<S>(handle: &slint::Weak<GbhieraUI>, msg: S)
 where
     S: Into<String>,
 {